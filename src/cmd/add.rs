@@ -1,19 +1,34 @@
-use std::{error::Error, path::Path};
+use std::{error::Error, fs, path::Path};
 
 use clap::{Parser, ValueHint};
+use git2::{Delta, Patch};
 
 use crate::{
-    git::Repo,
+    cmd::diff::render_colored,
+    git::{DiffOpts, LineEndings, Repo, Whitespace},
     term::{
+        self,
         node::prelude::*,
         render::{Render, TermRenderer},
-        select,
+        select::{self, SelectOpts},
     },
 };
 
 #[derive(Parser)]
 #[clap(about = "Add file contents to the index")]
 pub struct Opts {
+    #[clap(short, long, help = "Interactively choose hunks to stage")]
+    patch: bool,
+
+    #[clap(
+        short,
+        long,
+        conflicts_with = "patch",
+        requires = "targets",
+        help = "Allow adding ignored files"
+    )]
+    force: bool,
+
     #[clap(value_hint = ValueHint::AnyPath)]
     targets: Vec<String>,
 }
@@ -31,7 +46,86 @@ pub fn add_callback(path: &Path) {
     let _ = TermRenderer::default().renderln(&file_added(path));
 }
 
+fn add_patch(repo: &Repo, targets: &[String]) -> Result<(), Box<dyn Error>> {
+    // Whitespace changes must be kept, otherwise the hunks won't apply
+    let mut opts = DiffOpts::default().with_whitespace(Whitespace::Exact);
+
+    if !targets.is_empty() {
+        opts = opts.with_pathspec(targets);
+    }
+
+    let diff = repo.diff(opts)?;
+    let mut ui = TermRenderer::default();
+    let mut selected = vec![];
+
+    'deltas: for idx in 0..diff.deltas().len() {
+        let mut hunks = vec![];
+
+        // Like git, untracked files have to be added as a whole
+        let Some(patch) = Patch::from_diff(&diff, idx)?
+            .filter(|patch| patch.delta().status() != Delta::Untracked)
+        else {
+            selected.push(hunks);
+            continue;
+        };
+        let path = patch
+            .delta()
+            .new_file()
+            .path()
+            .map(|path| path.display().to_string())
+            .unwrap_or_default();
+
+        for hunk_idx in 0..patch.num_hunks() {
+            let (hunk, lines) = patch.hunk(hunk_idx)?;
+            let mut output = hunk.header().to_vec();
+
+            for line_idx in 0..lines {
+                let line = patch.line_in_hunk(hunk_idx, line_idx)?;
+
+                if matches!(line.origin(), '+' | '-' | ' ') {
+                    output.push(line.origin() as u8);
+                }
+
+                output.extend(line.content());
+            }
+
+            ui.renderln(&Node::Empty)?;
+            ui.renderln(&block!(
+                Node::Indicator(Indicator::Modified),
+                spacer!(),
+                text!(path.clone())
+            ))?;
+            render_colored(&mut ui, &output)?;
+
+            match term::choose("Stage this hunk?", vec!["yes", "no", "quit"])? {
+                "yes" => hunks.push(true),
+                "no" => hunks.push(false),
+                _ => {
+                    selected.push(hunks);
+                    break 'deltas;
+                }
+            }
+        }
+
+        selected.push(hunks);
+    }
+
+    let count = selected.iter().flatten().filter(|hunk| **hunk).count();
+
+    if count > 0 {
+        repo.apply_to_index(&diff, &selected)?;
+    }
+
+    println!("{count} hunk(s) added");
+
+    Ok(())
+}
+
 pub fn run(repo: Repo, opts: Opts) -> Result<(), Box<dyn Error>> {
+    if opts.patch {
+        return add_patch(&repo, &opts.targets);
+    }
+
     let targets = if opts.targets.is_empty() {
         let files = repo
             .status()?
@@ -39,7 +133,8 @@ pub fn run(repo: Repo, opts: Opts) -> Result<(), Box<dyn Error>> {
             .map(|p| p.path().map(|p| p.to_string()))
             .collect::<Result<Vec<_>, _>>()?;
 
-        select::multi(&files, Some("src diff {} --all".to_string()))?
+        let select_opts = SelectOpts::from_config(&repo.config()?, "add", "diff {} --all")?;
+        select::multi(&files, &select_opts)?
     } else {
         opts.targets
     };
@@ -49,10 +144,37 @@ pub fn run(repo: Repo, opts: Opts) -> Result<(), Box<dyn Error>> {
     }
 
     let mut index = repo.index()?;
+    let mut added = vec![];
 
-    let count = index.add(targets, add_callback)?;
+    let count = index.add(targets, opts.force, |path| {
+        add_callback(path);
+        added.push(path.to_path_buf());
+    })?;
     index.write()?;
 
+    if let Some(workdir) = repo.workdir() {
+        let autocrlf = repo.autocrlf()?;
+        let eol = repo.eol()?;
+        let mut ui = TermRenderer::default();
+
+        for path in added {
+            let Ok(content) = fs::read(workdir.join(&path)) else {
+                continue;
+            };
+
+            if let Some(warning) = LineEndings::detect(&content).warning(autocrlf, eol) {
+                ui.renderln(
+                    &block!(
+                        Node::Indicator(Indicator::Warning),
+                        spacer!(),
+                        text!(format!("{}: {warning}", path.display()))
+                    )
+                    .with_status(Status::Warning),
+                )?;
+            }
+        }
+    }
+
     if count > 0 {
         println!("{} file(s) added", count);
     }
@@ -1,12 +1,16 @@
 use std::error::Error;
 
 use clap::Parser;
+use git2::DiffStatsFormat;
 use gix::ObjectId;
 use inquire::ui::{Color, RenderConfig};
 
 use crate::{
-    cmd::add::add_callback,
-    git::Repo,
+    cmd::{
+        add::add_callback,
+        diff::{render_colored, render_diff},
+    },
+    git::{parse_date, Config, DateFormat, DiffOpts, Repo},
     term::{
         self,
         node::prelude::*,
@@ -23,15 +27,53 @@ pub struct Opts {
     #[clap(short, long, help = "Amend without prompting")]
     yes: bool,
 
+    #[clap(short = 'S', long, help = "Sign the commit (overrides commit.gpgsign)")]
+    sign: bool,
+
+    #[clap(
+        long,
+        conflicts_with = "sign",
+        help = "Don't sign the commit (overrides commit.gpgsign)"
+    )]
+    no_sign: bool,
+
+    #[clap(
+        long,
+        help = "Use the configured user as author instead of the original author"
+    )]
+    reset_author: bool,
+
+    #[clap(
+        long,
+        help = "Override the author date (e.g. '2024-01-31 12:00', 'now', '2.days.ago')"
+    )]
+    date: Option<String>,
+
     #[clap(help = "Commit message")]
     message: Option<String>,
 }
 
+impl Opts {
+    fn sign(&self) -> Option<bool> {
+        match (self.sign, self.no_sign) {
+            (true, _) => Some(true),
+            (_, true) => Some(false),
+            _ => None,
+        }
+    }
+}
+
 pub fn run(repo: Repo, opts: Opts) -> Result<(), Box<dyn Error>> {
+    let sign = opts.sign();
+    let date = opts
+        .date
+        .as_deref()
+        .map(|date| parse_date(date).ok_or_else(|| format!("invalid date: {date}")))
+        .transpose()?;
     let mut index = repo.index()?;
 
     if opts.add_all {
-        index.add(["."], add_callback)?;
+        index.add(["."], false, add_callback)?;
         index.write()?;
     }
 
@@ -44,11 +86,29 @@ pub fn run(repo: Repo, opts: Opts) -> Result<(), Box<dyn Error>> {
 
         if !opts.yes {
             ui.renderln(&multi_line!(
-                dimmed!(commit.headers_ui()),
+                dimmed!(commit.headers_ui(DateFormat::Local)),
                 spacer!(),
                 text!(commit.message_formatted())
             ))?;
 
+            let old_tree = commit.find_tree()?;
+            let diff = repo.diff(DiffOpts::default().with_trees(&old_tree, &tree))?;
+
+            ui.renderln(&Node::Empty)?;
+
+            let stats = diff.stats()?;
+
+            if stats.files_changed() == 0 {
+                ui.renderln(&dimmed!(text!("No new changes")))?;
+            } else {
+                let buf = stats.to_buf(DiffStatsFormat::FULL, term::width())?;
+                let stat = String::from_utf8_lossy(&buf).trim_end().to_string();
+
+                ui.renderln(&text!(stat))?;
+                ui.renderln(&Node::Empty)?;
+                render_colored(&mut ui, &render_diff(&diff, None)?)?;
+            }
+
             let mut config = RenderConfig::default_colored();
             config.prompt.fg = Some(Color::LightCyan);
 
@@ -62,7 +122,17 @@ pub fn run(repo: Repo, opts: Opts) -> Result<(), Box<dyn Error>> {
             Some(message) => message,
             None => commit.message()?.to_string(),
         };
-        let oid = repo.create_commit(&tree, &message, Some(&parent))?;
+        let author = if opts.reset_author {
+            Config::open_default()?.user.signature()?.to_owned()
+        } else {
+            commit.author().to_owned()
+        };
+        let author = git2::Signature::new(
+            author.name().unwrap_or_default(),
+            author.email().unwrap_or_default(),
+            &date.unwrap_or(author.when()),
+        )?;
+        let oid = repo.create_commit(&tree, &message, Some(&[&parent]), Some(&author), sign)?;
 
         (oid, message)
     };
@@ -2,20 +2,136 @@ use std::error::Error;
 
 use clap::Parser;
 
-use crate::git::Repo;
+use crate::{
+    git::{Commit, Optional, Pattern, Repo},
+    term::{
+        self,
+        node::prelude::*,
+        render::{Render, TermRenderer},
+    },
+};
 
 #[derive(Parser)]
-#[clap(about = "Create a branch")]
+#[clap(about = "Create, rename or delete a branch")]
 pub struct Opts {
-    #[clap(help = "Branch name")]
-    branch: String,
+    #[clap(help = "Branch name", required_unless_present_any = ["delete", "rename"])]
+    branch: Option<String>,
+
+    #[clap(help = "Branch, commit or pattern (e.g. main~3) to start the branch at")]
+    start_point: Option<String>,
+
+    #[clap(
+        short,
+        long,
+        help = "Checkout the new branch (default when no start point is given)"
+    )]
+    checkout: bool,
+
+    #[clap(short, long, value_name = "BRANCH", help = "Delete a branch")]
+    delete: Option<String>,
+
+    #[clap(
+        short = 'm',
+        long = "move",
+        num_args = 2,
+        value_names = ["OLD", "NEW"],
+        help = "Rename a branch"
+    )]
+    rename: Option<Vec<String>>,
+
+    #[clap(
+        short = 'D',
+        long,
+        help = "Delete without prompting (even if not fully merged) or overwrite on rename"
+    )]
+    force: bool,
+}
+
+pub fn resolve_commit<'a>(repo: &'a Repo, spec: &str) -> Result<Commit<'a>, Box<dyn Error>> {
+    let (_, pattern) = Pattern::parse(spec).map_err(|_| format!("Invalid commit: {spec}"))?;
+    let oid = pattern
+        .resolve(repo)?
+        .ok_or_else(|| format!("Commit not found: {spec}"))?;
+
+    Ok(repo.find_commit(oid)?)
+}
+
+fn delete(repo: &Repo, name: &str, force: bool) -> Result<(), Box<dyn Error>> {
+    if repo.head()?.shorthand()? == name {
+        return Err(format!("Cannot delete the checked out branch: {name}").into());
+    }
+
+    if !force {
+        let branch = repo.find_branch(name)?;
+        let base = match branch.upstream().optional()? {
+            Some(upstream) => upstream.target()?,
+            None => repo.head()?.target()?,
+        };
+        let (ahead, _) = repo.ahead_behind(branch.target()?, base)?;
+
+        if ahead > 0 {
+            return Err(format!(
+                "Branch is not fully merged ({ahead} commit(s) ahead), use --force to delete: {name}"
+            )
+            .into());
+        }
+
+        if !term::confirm(&format!("Delete branch {name}?"))? {
+            return Ok(());
+        }
+    }
+
+    repo.delete_branch(name)?;
+
+    let mut ui = TermRenderer::default();
+    ui.renderln(&message_with_icon(
+        Icon::Check,
+        format!("Branch deleted: {name}"),
+    ))?;
+
+    Ok(())
+}
+
+fn rename(repo: &Repo, old: &str, new: &str, force: bool) -> Result<(), Box<dyn Error>> {
+    repo.rename_branch(old, new, force)?;
+
+    let mut ui = TermRenderer::default();
+    ui.renderln(&message_with_icon(
+        Icon::Check,
+        format!("Branch renamed: {old} → {new}"),
+    ))?;
+
+    Ok(())
 }
 
 pub fn run(repo: Repo, opts: Opts) -> Result<(), Box<dyn Error>> {
+    if let Some(name) = opts.delete {
+        return delete(&repo, &name, opts.force);
+    }
+
+    if let Some([old, new]) = opts.rename.as_deref() {
+        return rename(&repo, old, new, opts.force);
+    }
+
+    let checkout = opts.checkout || opts.start_point.is_none();
+
     {
-        let head = repo.head()?;
-        let target = head.find_commit()?;
-        let branch = repo.create_branch(&opts.branch, &target)?;
+        let name = opts.branch.ok_or("No branch specified")?;
+        let target = match opts.start_point.as_deref() {
+            Some(spec) => resolve_commit(&repo, spec)?,
+            None => repo.head()?.find_commit()?,
+        };
+        let branch = repo.create_branch(&name, &target)?;
+
+        if !checkout {
+            let mut ui = TermRenderer::default();
+            ui.renderln(&message_with_icon(
+                Icon::Check,
+                format!("Branch created: {name}"),
+            ))?;
+
+            return Ok(());
+        }
 
         repo.checkout(&branch.into())?;
     }
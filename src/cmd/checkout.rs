@@ -1,27 +1,50 @@
 use std::error::Error;
 
-use clap::Parser;
+use clap::{Parser, ValueHint};
+use gix::ObjectId;
 
 use crate::{
-    git::{Branch, CheckoutError, Optional, Ref, RemoteOpts, Repo},
+    cmd::branch::resolve_commit,
+    git::{Branch, CheckoutError, Optional, Pattern, Ref, RemoteOpts, Repo},
     term::{
         node::prelude::*,
         render::{Render, TermRenderer},
-        select,
+        select::{self, SelectOpts},
     },
 };
 
 #[derive(Parser)]
 #[clap(about = "Checkout a branch", alias = "use")]
 pub struct Opts {
-    #[clap(help = "Branch name")]
+    #[clap(help = "Branch name (or start point when creating a branch)")]
     branch: Option<String>,
+
+    #[clap(
+        short = 'b',
+        long = "create",
+        value_name = "BRANCH",
+        help = "Create a new branch and check it out"
+    )]
+    create: Option<String>,
+
+    #[clap(
+        short,
+        long,
+        help = "Overwrite local modifications when restoring paths"
+    )]
+    force: bool,
+
+    #[clap(last = true, value_hint = ValueHint::AnyPath, help = "Paths to restore")]
+    paths: Vec<String>,
 }
 
 impl Opts {
     pub fn with_branch(branch: String) -> Self {
         Self {
             branch: Some(branch),
+            create: None,
+            force: false,
+            paths: vec![],
         }
     }
 }
@@ -34,15 +57,35 @@ pub fn try_checkout(repo: &Repo, reference: &Ref<'_>) -> Result<bool, git2::Erro
     }
 }
 
-fn branch_names(repo: &Repo) -> Result<Vec<String>, Box<dyn Error>> {
-    let branches = repo.branches()?;
-    Ok(branches
-        .map(|result| {
-            result
-                .map_err(Into::into)
-                .and_then(|branch| branch.name().map(ToOwned::to_owned))
-        })
-        .collect::<Result<Vec<_>, _>>()?)
+/// Formats the branches for the picker as `name<TAB>short-oid title<TAB>ahead/behind`, the name
+/// is kept as the first field so it can be parsed back after selecting.
+fn branch_lines(repo: &Repo) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut lines = vec![];
+
+    for branch in repo.branches()? {
+        let branch = branch?;
+        let target = branch.target()?;
+        let commit = repo.find_commit(target)?;
+        let title = commit
+            .message()
+            .unwrap_or_default()
+            .lines()
+            .next()
+            .unwrap_or_default();
+        let mut line = format!("{}\t{} {title}", branch.name()?, &target.to_string()[..7]);
+
+        if let Some(upstream) = branch.upstream().optional()? {
+            let (ahead, behind) = repo.ahead_behind(target, upstream.target()?)?;
+
+            if ahead > 0 || behind > 0 {
+                line.push_str(&format!("\t↑{ahead} ↓{behind}"));
+            }
+        }
+
+        lines.push(line);
+    }
+
+    Ok(lines)
 }
 
 fn find_remote_branch<'a>(
@@ -70,20 +113,108 @@ fn find_remote_branch<'a>(
     Ok(None)
 }
 
+fn create_and_checkout(
+    repo: &Repo,
+    name: &str,
+    start_point: Option<&str>,
+) -> Result<(), Box<dyn Error>> {
+    if repo.find_branch(name).optional()?.is_some() {
+        return Err(format!("Branch already exists: {name}").into());
+    }
+
+    let target = match start_point {
+        Some(spec) => resolve_commit(repo, spec)?,
+        None => repo.head()?.find_commit()?,
+    };
+    let branch = repo.create_branch(name, &target)?;
+
+    Ok(repo.checkout(&branch.into())?)
+}
+
+fn restore_paths(
+    repo: &Repo,
+    source: Option<&str>,
+    paths: &[String],
+    force: bool,
+) -> Result<(), Box<dyn Error>> {
+    let tree = match source {
+        Some(spec) => resolve_commit(repo, spec)?.find_tree()?,
+        None => repo.head()?.find_tree()?,
+    };
+
+    match repo.checkout_paths(&tree, paths, force) {
+        Ok(()) => {}
+        Err(CheckoutError::Conflict(_)) => {
+            return Err("Paths have local modifications (use --force to overwrite)".into())
+        }
+        Err(CheckoutError::Git(e)) => return Err(e.into()),
+    }
+
+    let mut ui = TermRenderer::default();
+    ui.renderln(&message_with_icon(
+        Icon::Check,
+        format!("{} path(s) restored", paths.len()),
+    ))?;
+
+    Ok(())
+}
+
+fn resolve_detached(repo: &Repo, name: &str) -> Result<Option<git2::Oid>, git2::Error> {
+    match Pattern::parse(name) {
+        Ok((_, pattern)) => pattern.resolve(repo),
+        Err(_) => Ok(None),
+    }
+}
+
+fn checkout_detached(repo: &Repo, name: &str) -> Result<(), Box<dyn Error>> {
+    let commit = resolve_commit(repo, name)?;
+    repo.checkout_detached(&commit)?;
+
+    let mut ui = TermRenderer::default();
+    ui.renderln(&multi_line!(
+        block!(
+            text!("You are in 'detached HEAD' state at"),
+            spacer!(),
+            Node::Attribute(Attribute::CommitShort(ObjectId::try_from(
+                commit.id().as_bytes()
+            )?))
+        )
+        .with_status(Status::Warning),
+        dimmed!(text!(
+            "Create a branch to keep any commits you make with: src branch <name>"
+        ))
+    ))?;
+
+    super::status::run(gix::open(repo.path())?, super::status::Opts::default())
+}
+
 pub fn run(mut repo: Repo, opts: Opts) -> Result<(), Box<dyn Error>> {
+    if !opts.paths.is_empty() {
+        return restore_paths(&repo, opts.branch.as_deref(), &opts.paths, opts.force);
+    }
+
+    if let Some(name) = opts.create {
+        create_and_checkout(&repo, &name, opts.branch.as_deref())?;
+        return super::status::run(gix::open(repo.path())?, super::status::Opts::default());
+    }
+
     let branch_name = match opts.branch {
         Some(branch) => branch,
         None => match select::single(
-            &branch_names(&repo)?,
-            Some("src list commit {}".to_string()),
+            &branch_lines(&repo)?,
+            &SelectOpts::from_config(&repo.config()?, "checkout", "list commit {1}")?
+                .with_delimiter("\t"),
         )? {
-            Some(branch) => branch,
+            Some(line) => line.split('\t').next().unwrap_or_default().to_string(),
             None => return Err("No branch selected".into()),
         },
     };
 
     let branch = match repo.find_branch(&branch_name).optional()? {
         Some(branch) => branch,
+        None if resolve_detached(&repo, &branch_name)?.is_some() => {
+            return checkout_detached(&repo, &branch_name);
+        }
         None => match find_remote_branch(&repo, &branch_name) {
             Ok(Some(branch)) => branch,
             Ok(None) => return Err("Branch not found".into()),
@@ -1,11 +1,13 @@
-use std::{env::current_dir, error::Error, fs};
+use std::{error::Error, fs, path::Path};
 
 use clap::Parser;
 use gix::{bstr::BStr, progress::DoOrDiscard, remote::Direction};
+use resolve_path::PathResolveExt;
 
 use crate::{
     progress,
     term::{
+        cooperate,
         node::prelude::*,
         render::{Render, TermRenderer},
     },
@@ -27,7 +29,8 @@ fn convert_uri(uri: &str) -> Option<String> {
     None
 }
 
-pub fn run(opts: Opts) -> Result<(), Box<dyn Error>> {
+/// Clones into a directory named after the repository inside `dir`.
+pub fn run(dir: &Path, opts: Opts) -> Result<(), Box<dyn Error>> {
     let root = progress::tree();
     let sub_progress = root.add_child("Clone");
     let handle = progress::setup_line_renderer(&root);
@@ -40,7 +43,7 @@ pub fn run(opts: Opts) -> Result<(), Box<dyn Error>> {
         .map(|component| component.trim_end_matches(".git"))
         .unwrap_or_default();
 
-    let path = current_dir()?.join(name);
+    let path = dir.resolve().join(name);
 
     if path.exists() {
         return Err(format!("Directory already exists: {}", path.display()).into());
@@ -49,13 +52,16 @@ pub fn run(opts: Opts) -> Result<(), Box<dyn Error>> {
     fs::create_dir_all(&path)?;
 
     let url = gix::url::parse(BStr::new(uri.as_bytes()))?;
+    let _cooperate = cooperate();
     let mut prepared = gix::prepare_clone(url, &path)?;
     let (mut prepare_checkout, _) =
         prepared.fetch_then_checkout(&mut progress, &gix::interrupt::IS_INTERRUPTED)?;
     let (repo, _) =
         prepare_checkout.main_worktree(&mut progress, &gix::interrupt::IS_INTERRUPTED)?;
 
-    handle.shutdown_and_wait();
+    if let Some(handle) = handle {
+        handle.shutdown_and_wait();
+    }
 
     repo.find_default_remote(Direction::Fetch)
         .transpose()?
@@ -1,12 +1,14 @@
-use std::error::Error;
+use std::{error::Error, fs};
 
 use clap::Parser;
 use git2::ErrorCode;
 
 use crate::{
     cmd::add::add_callback,
-    git::{DiffOpts, Repo},
+    cmd::branch::resolve_commit,
+    git::{message_trailers, split_message, Config, DiffOpts, Index, Repo},
     term::{
+        self,
         node::prelude::*,
         render::{Render, TermRenderer},
     },
@@ -18,34 +20,291 @@ pub struct Opts {
     #[clap(short, long, help = "Add all changes")]
     add_all: bool,
 
+    #[clap(
+        short,
+        long,
+        conflicts_with = "add_all",
+        help = "Commit exactly what is staged, ignoring changes staged by hooks"
+    )]
+    keep_index: bool,
+
     #[clap(short, long, help = "Create a branch")]
     branch: bool,
 
-    #[clap(help = "Commit message")]
-    pub message: String,
+    #[clap(short, long, help = "Bypass the pre-commit and commit-msg hooks")]
+    no_verify: bool,
+
+    #[clap(long, help = "Allow recording a commit without any changes")]
+    allow_empty: bool,
+
+    #[clap(short = 'S', long, help = "Sign the commit (overrides commit.gpgsign)")]
+    sign: bool,
+
+    #[clap(
+        long,
+        conflicts_with = "sign",
+        help = "Don't sign the commit (overrides commit.gpgsign)"
+    )]
+    no_sign: bool,
+
+    #[clap(
+        short = 'C',
+        long,
+        value_name = "COMMIT",
+        conflicts_with = "reedit_message",
+        help = "Reuse the message of the given commit"
+    )]
+    reuse_message: Option<String>,
+
+    #[clap(
+        short = 'c',
+        long,
+        value_name = "COMMIT",
+        help = "Reuse and edit the message of the given commit"
+    )]
+    reedit_message: Option<String>,
+
+    #[clap(
+        long,
+        help = "Conventional commit scope, e.g. `src feat --scope api` (fix, feat, refactor, chore)"
+    )]
+    scope: Option<String>,
+
+    #[clap(
+        short,
+        long,
+        help = "Add a Signed-off-by trailer with your name and email"
+    )]
+    signoff: bool,
+
+    #[clap(
+        long = "co-author",
+        value_name = "NAME <EMAIL>",
+        value_parser = parse_co_author,
+        help = "Add a Co-authored-by trailer (can be repeated)"
+    )]
+    co_authors: Vec<String>,
+
+    #[clap(help = "Commit message (opens an editor when omitted)")]
+    pub message: Option<String>,
+
+    #[clap(skip)]
+    prefix: Option<String>,
+}
+
+impl Opts {
+    fn sign(&self) -> Option<bool> {
+        match (self.sign, self.no_sign) {
+            (true, _) => Some(true),
+            (_, true) => Some(false),
+            _ => None,
+        }
+    }
 }
 
+/// Derives a branch name from the subject, the body and any trailers are ignored.
 fn branch_name(message: &str) -> String {
-    if let Some((prefix, name)) = message.split_once(':') {
-        return format!(
-            "{}/{}",
-            prefix.trim().replace([' ', '/'], "-"),
-            name.trim().replace([' ', '/'], "-"),
-        );
+    let subject = split_message(message)
+        .0
+        .lines()
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    if let Some((prefix, name)) = subject.split_once(':') {
+        // `feat(api): ...` becomes `feat/api/...`
+        let prefix = match prefix
+            .trim()
+            .strip_suffix(')')
+            .and_then(|p| p.split_once('('))
+        {
+            Some((ty, scope)) => format!(
+                "{}/{}",
+                ty.trim().replace([' ', '/'], "-"),
+                scope.trim().replace([' ', '/'], "-")
+            ),
+            None => prefix.trim().replace([' ', '/'], "-"),
+        };
+
+        return format!("{prefix}/{}", name.trim().replace([' ', '/'], "-"));
     }
 
-    message.trim().replace(' ', "-")
+    subject.trim().replace(' ', "-")
 }
 
-pub fn run(repo: Repo, opts: Opts) -> Result<(), Box<dyn Error>> {
-    if opts.branch {
-        let head = repo.head()?;
-        let commit = head.find_commit()?;
-        let branch = repo.create_branch(&branch_name(&opts.message), &commit)?;
+fn commit_message(repo: &Repo, opts: &Opts) -> Result<String, Box<dyn Error>> {
+    if opts.scope.is_some() && opts.prefix.is_none() {
+        return Err("--scope can only be used with fix, feat, refactor or chore".into());
+    }
 
-        repo.checkout(&branch.into())?;
+    if let Some(spec) = opts.reuse_message.as_deref() {
+        return Ok(resolve_commit(repo, spec)?.message()?.to_string());
     }
 
+    if let Some(spec) = opts.reedit_message.as_deref() {
+        let config = Config::open_default()?;
+        let message = term::edit(
+            config.core.editor.as_deref(),
+            resolve_commit(repo, spec)?.message()?,
+        )?;
+
+        if message.is_empty() {
+            return Err("Aborting commit due to empty commit message".into());
+        }
+
+        return Ok(message);
+    }
+
+    let message = match opts.message.clone() {
+        Some(message) => message,
+        None => {
+            let config = Config::open_default()?;
+            let message = term::edit(config.core.editor.as_deref(), &message_template(repo)?)?;
+
+            if message.is_empty() {
+                return Err("Aborting commit due to empty commit message".into());
+            }
+
+            message
+        }
+    };
+
+    Ok(match (opts.prefix.as_deref(), opts.scope.as_deref()) {
+        (Some(prefix), Some(scope)) => format!("{prefix}({scope}): {message}"),
+        (Some(prefix), None) => format!("{prefix}: {message}"),
+        _ => message,
+    })
+}
+
+/// Validates and normalizes a `Name <email>` identity.
+fn parse_co_author(value: &str) -> Result<String, String> {
+    let invalid = || format!("invalid co-author '{value}', expected 'Name <email>'");
+    let (name, email) = value
+        .trim()
+        .strip_suffix('>')
+        .and_then(|value| value.rsplit_once('<'))
+        .ok_or_else(invalid)?;
+    let (name, email) = (name.trim(), email.trim());
+
+    if name.is_empty() || !email.contains('@') || email.contains(['<', '>', ' ']) {
+        return Err(invalid());
+    }
+
+    Ok(format!("{name} <{email}>"))
+}
+
+/// Appends a trailer unless the message already has it, trailers are grouped in the last
+/// paragraph.
+fn append_trailer(message: &str, key: &str, value: &str) -> String {
+    let message = message.trim_end();
+    let trailers = message_trailers(message);
+
+    if trailers
+        .iter()
+        .any(|(k, v)| k.eq_ignore_ascii_case(key) && *v == value)
+    {
+        return message.to_string();
+    }
+
+    let separator = if trailers.is_empty() { "\n\n" } else { "\n" };
+    format!("{message}{separator}{key}: {value}")
+}
+
+fn message_template(repo: &Repo) -> Result<String, Box<dyn Error>> {
+    let mut template = String::from(
+        "\n# Please enter the commit message for your changes. Lines starting\n\
+         # with '#' will be ignored, and an empty message aborts the commit.\n#\n\
+         # Changes to be committed:\n",
+    );
+
+    for entry in repo.status()?.entries().filter(|entry| entry.is_staged()) {
+        template.push_str(&format!("#\t{}\n", entry.path()?));
+    }
+
+    Ok(template)
+}
+
+pub fn run_hook(
+    repo: &Repo,
+    ui: &mut impl Render,
+    name: &str,
+    args: &[&str],
+) -> Result<(), Box<dyn Error>> {
+    let Some(result) = repo.run_hook(name, args)? else {
+        return Ok(());
+    };
+
+    if result.success() {
+        return Ok(());
+    }
+
+    let output = result.output();
+
+    ui.renderln(
+        &block!(
+            Node::Indicator(Indicator::Deleted),
+            spacer!(),
+            text!(format!("{name} hook failed"))
+        )
+        .with_status(Status::Error),
+    )?;
+
+    if !output.is_empty() {
+        ui.renderln(&dimmed!(text!(output)))?;
+    }
+
+    Err(result.into_error().into())
+}
+
+fn run_commit_hooks(
+    repo: &Repo,
+    ui: &mut impl Render,
+    message: String,
+) -> Result<String, Box<dyn Error>> {
+    run_hook(repo, ui, "pre-commit", &[])?;
+
+    let path = repo.path().join("COMMIT_EDITMSG");
+    fs::write(&path, &message)?;
+    run_hook(
+        repo,
+        ui,
+        "commit-msg",
+        &[path.to_str().ok_or("invalid path")?],
+    )?;
+
+    // The commit-msg hook is allowed to edit the message
+    Ok(fs::read_to_string(&path)?.trim().to_string())
+}
+
+/// Runs the commit hooks and returns the tree to commit.
+///
+/// Hooks may stage additional changes, those are included unless `--keep-index` is used in which
+/// case the tree is exactly what was staged beforehand. The working directory is never touched.
+fn commit_tree(
+    repo: &Repo,
+    ui: &mut impl Render,
+    index: &mut Index,
+    message: String,
+    opts: &Opts,
+) -> Result<(git2::Oid, String), Box<dyn Error>> {
+    let staged = index.write_tree()?;
+
+    if opts.no_verify {
+        ui.renderln(&dimmed!(text!("Skipping pre-commit and commit-msg hooks")))?;
+        return Ok((staged, message));
+    }
+
+    let message = run_commit_hooks(repo, ui, message)?;
+
+    if opts.keep_index {
+        return Ok((staged, message));
+    }
+
+    index.reload()?;
+    Ok((index.write_tree()?, message))
+}
+
+pub fn run(repo: Repo, opts: Opts) -> Result<(), Box<dyn Error>> {
+    let mut ui = TermRenderer::default();
     let old_tree = match repo.head() {
         Ok(head) => Some(head.find_tree()?),
         Err(e) if e.code() == ErrorCode::UnbornBranch => None,
@@ -55,19 +314,58 @@ pub fn run(repo: Repo, opts: Opts) -> Result<(), Box<dyn Error>> {
     let mut index = repo.index()?;
 
     if opts.add_all {
-        index.add(["."], add_callback)?;
+        index.add(["."], false, add_callback)?;
         index.write()?;
     }
 
-    let tree = repo.find_tree(index.write_tree()?)?;
-    let oid = repo.create_commit(&tree, &opts.message, None)?;
+    if let Some(tree) = old_tree.as_ref() {
+        if !opts.allow_empty && index.write_tree()? == tree.0.id() {
+            ui.renderln(
+                &text!("Nothing to commit (use --allow-empty to commit anyway)")
+                    .with_status(Status::Warning),
+            )?;
+            return Ok(());
+        }
+    }
+
+    // Resolved after staging so the editor template lists everything being committed
+    let mut message = commit_message(&repo, &opts)?;
+    let branch = opts.branch.then(|| branch_name(&message));
+
+    for co_author in &opts.co_authors {
+        message = append_trailer(&message, "Co-authored-by", co_author);
+    }
+
+    if opts.signoff {
+        let user = Config::open_default()?.user;
+        let value = format!(
+            "{} <{}>",
+            user.name.as_deref().unwrap_or_default(),
+            user.email
+        );
+
+        message = append_trailer(&message, "Signed-off-by", &value);
+    }
+
+    if let Some(branch) = branch {
+        let head = repo.head()?;
+        let commit = head.find_commit()?;
+        let branch = repo.create_branch(&branch, &commit)?;
+
+        repo.checkout(&branch.into())?;
+    }
+
+    let (tree, message) = commit_tree(&repo, &mut ui, &mut index, message, &opts)?;
+    let tree = repo.find_tree(tree)?;
+    let oid = repo.create_commit(&tree, &message, None, None, opts.sign())?;
 
     if old_tree.is_none() {
         repo.create_ref("refs/heads/main", oid)?;
     }
 
     repo.head()?
-        .set_target(oid, &format!("commit: {}", opts.message))?;
+        .set_target(oid, &format!("commit: {message}"))?;
+    repo.cleanup_merge()?;
 
     let mut opts = DiffOpts::default();
 
@@ -77,8 +375,6 @@ pub fn run(repo: Repo, opts: Opts) -> Result<(), Box<dyn Error>> {
 
     let diff = repo.diff(opts)?;
     let stats = diff.stats()?;
-
-    let mut ui = TermRenderer::default();
     let mut children = vec![];
 
     if stats.insertions() > 0 {
@@ -119,6 +415,122 @@ pub fn run(repo: Repo, opts: Opts) -> Result<(), Box<dyn Error>> {
 }
 
 pub fn with_prefix(prefix: &str, repo: Repo, mut opts: Opts) -> Result<(), Box<dyn Error>> {
-    opts.message = format!("{prefix}: {}", opts.message);
+    opts.prefix = Some(prefix.to_string());
     run(repo, opts)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    #[test]
+    fn test_branch_name() {
+        assert_eq!(branch_name("add endpoint"), "add-endpoint");
+        assert_eq!(branch_name("feat: add endpoint"), "feat/add-endpoint");
+        assert_eq!(
+            branch_name("feat(api): add endpoint"),
+            "feat/api/add-endpoint"
+        );
+        assert_eq!(
+            branch_name("feat: add endpoint\n\nSigned-off-by: Jane <jane@example.com>"),
+            "feat/add-endpoint"
+        );
+        assert_eq!(
+            branch_name("add endpoint\nfor users\n\nSome details.\n\nCo-authored-by: Jane <jane@example.com>"),
+            "add-endpoint-for-users"
+        );
+    }
+
+    #[test]
+    fn test_signoff() {
+        let jane = "Jane <jane@example.com>";
+        let trailer = format!("Signed-off-by: {jane}");
+        let message = append_trailer("feat: add endpoint\n", "Signed-off-by", jane);
+
+        assert_eq!(message, format!("feat: add endpoint\n\n{trailer}"));
+        assert_eq!(append_trailer(&message, "Signed-off-by", jane), message);
+        assert_eq!(
+            append_trailer("Fix bug\n\nFixes: #12", "Signed-off-by", jane),
+            format!("Fix bug\n\nFixes: #12\n{trailer}")
+        );
+        assert_eq!(
+            append_trailer(&message, "Signed-off-by", "John <john@example.com>")
+                .matches(&trailer)
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_co_author() {
+        assert_eq!(
+            parse_co_author(" Jane Doe  <jane@example.com> ").unwrap(),
+            "Jane Doe <jane@example.com>"
+        );
+        assert!(parse_co_author("Jane Doe").is_err());
+        assert!(parse_co_author("<jane@example.com>").is_err());
+        assert!(parse_co_author("Jane <jane>").is_err());
+
+        let opts = Opts::try_parse_from([
+            "commit",
+            "--co-author",
+            "Jane <jane@example.com>",
+            "--co-author",
+            "John <john@example.com>",
+            "test",
+        ])
+        .unwrap();
+        assert_eq!(opts.co_authors.len(), 2);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_keep_index_ignores_hook_changes() {
+        use std::os::unix::fs::PermissionsExt;
+
+        use crate::git::fixture;
+
+        // The hook stages with git itself
+        if which::which("git").is_err() {
+            return;
+        }
+
+        let (dir, repo) = fixture::init();
+        let repo = Repo::from(repo);
+        let hook = dir.path().join(".git/hooks/pre-commit");
+
+        fs::create_dir_all(hook.parent().unwrap()).unwrap();
+        fs::write(
+            &hook,
+            "#!/bin/sh\necho generated > generated && git add generated\n",
+        )
+        .unwrap();
+        fs::set_permissions(&hook, fs::Permissions::from_mode(0o755)).unwrap();
+        fs::write(dir.path().join("staged"), "staged\n").unwrap();
+        fs::write(dir.path().join("unstaged"), "unstaged\n").unwrap();
+
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("staged")).unwrap();
+        index.write().unwrap();
+
+        let paths = |oid| {
+            let tree = repo.find_tree(oid).unwrap();
+            tree.0
+                .iter()
+                .map(|entry| entry.name().unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+        let mut ui = TermRenderer::new(String::new());
+
+        let opts = Opts::try_parse_from(["commit", "--keep-index", "test"]).unwrap();
+        let (tree, _) = commit_tree(&repo, &mut ui, &mut index, "test".into(), &opts).unwrap();
+        assert_eq!(paths(tree), vec!["staged"]);
+        assert!(Opts::try_parse_from(["commit", "--keep-index", "--add-all", "test"]).is_err());
+
+        let opts = Opts::try_parse_from(["commit", "test"]).unwrap();
+        let (tree, _) = commit_tree(&repo, &mut ui, &mut index, "test".into(), &opts).unwrap();
+        assert_eq!(paths(tree), vec!["generated", "staged"]);
+    }
+}
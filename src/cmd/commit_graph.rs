@@ -0,0 +1,37 @@
+use std::error::Error;
+
+use clap::Parser;
+
+use crate::{
+    git::Repo,
+    term::{
+        node::prelude::*,
+        render::{Render, TermRenderer},
+    },
+};
+
+#[derive(Parser)]
+#[clap(about = "Manage the commit-graph file used to speed up history traversal")]
+pub struct Opts {
+    #[clap(subcommand)]
+    cmd: Cmd,
+}
+
+#[derive(Parser)]
+pub enum Cmd {
+    #[clap(about = "Write or update the commit-graph for all reachable commits")]
+    Write,
+}
+
+pub fn run(repo: Repo, opts: Opts) -> Result<(), Box<dyn Error>> {
+    match opts.cmd {
+        Cmd::Write => {
+            repo.write_commit_graph()?;
+
+            let mut ui = TermRenderer::default();
+            ui.renderln(&message_with_icon(Icon::Check, "Commit-graph written"))?;
+
+            Ok(())
+        }
+    }
+}
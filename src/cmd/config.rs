@@ -0,0 +1,124 @@
+use std::error::Error;
+
+use clap::Parser;
+use git2::ConfigLevel;
+
+use crate::{
+    git::{Optional, Repo},
+    term::{
+        node::prelude::*,
+        render::{Render, TermRenderer},
+    },
+};
+
+#[derive(Parser)]
+#[clap(about = "Read and write the configuration")]
+pub struct Opts {
+    #[clap(subcommand)]
+    cmd: Cmd,
+
+    #[clap(
+        long,
+        global = true,
+        conflicts_with = "global",
+        help = "Only use the repository config"
+    )]
+    local: bool,
+
+    #[clap(long, global = true, help = "Only use the global config")]
+    global: bool,
+}
+
+#[derive(Parser)]
+pub enum Cmd {
+    #[clap(about = "Show the value of a variable")]
+    Get {
+        #[clap(help = "Name of the variable, e.g. push.autoSetupRemote")]
+        key: String,
+    },
+
+    #[clap(about = "Set the value of a variable")]
+    Set {
+        #[clap(help = "Name of the variable, e.g. push.autoSetupRemote")]
+        key: String,
+
+        #[clap(help = "New value")]
+        value: String,
+    },
+
+    #[clap(
+        long_flag = "list",
+        about = "List all variables along with their source (also `--list`)"
+    )]
+    List,
+}
+
+fn source(level: ConfigLevel) -> String {
+    match level {
+        ConfigLevel::Highest => "cli".to_string(),
+        level => format!("{level:?}").to_lowercase(),
+    }
+}
+
+fn list(ui: &mut impl Render, config: &git2::Config) -> Result<(), Box<dyn Error>> {
+    let mut entries = config.entries(None)?;
+
+    while let Some(entry) = entries.next() {
+        let entry = entry?;
+        let name = std::str::from_utf8(entry.name_bytes())?;
+        let value = entry
+            .value_bytes()
+            .map(String::from_utf8_lossy)
+            .unwrap_or_default();
+
+        ui.renderln(&column!(
+            block!(
+                dimmed!(text!(format!("{:<12}", source(entry.level())))),
+                text!(name.to_string())
+            ),
+            text!(value.to_string())
+        ))?;
+    }
+
+    Ok(())
+}
+
+pub fn run(repo: Repo, opts: Opts) -> Result<(), Box<dyn Error>> {
+    // Without a scope, writes go to the repository config like git does
+    let mut config = match (opts.local, opts.global) {
+        (true, _) => repo.config()?.open_level(ConfigLevel::Local)?,
+        (_, true) => repo.config()?.open_level(ConfigLevel::Global)?,
+        _ => repo.config()?,
+    };
+
+    let mut ui = TermRenderer::default();
+
+    match opts.cmd {
+        Cmd::Get { key } => match config.get_string(&key).optional()? {
+            Some(value) => Ok(ui.renderln(&text!(value))?),
+            None => Err(format!("{key} is not set").into()),
+        },
+        Cmd::Set { key, value } => {
+            config.set_str(&key, &value)?;
+
+            Ok(ui.renderln(&message_with_icon(
+                Icon::Check,
+                format!("{key} set to {value}"),
+            ))?)
+        }
+        Cmd::List => list(&mut ui, &config),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_list_flag() {
+        for args in [["config", "list"], ["config", "--list"]] {
+            let opts = Opts::try_parse_from(args).unwrap();
+            assert!(matches!(opts.cmd, Cmd::List));
+        }
+    }
+}
@@ -0,0 +1,101 @@
+use std::error::Error;
+
+use clap::Parser;
+use gix::ObjectId;
+
+use crate::{
+    cmd::status::{find_state, render_branch, render_state},
+    git::{Commit, Repo},
+    graph::Graph,
+    term::{
+        self,
+        node::prelude::*,
+        render::{Render, TermRenderer},
+    },
+};
+
+#[derive(Parser)]
+#[clap(about = "Show an overview of the repository")]
+pub struct Opts {
+    #[clap(
+        short = 'n',
+        long,
+        default_value_t = 5,
+        help = "Number of recent commits to show"
+    )]
+    max_count: usize,
+}
+
+fn commit_line(commit: &Commit<'_>) -> Result<Node, Box<dyn Error>> {
+    Ok(block!(
+        spacer!(),
+        spacer!(),
+        Node::Attribute(Attribute::CommitShort(ObjectId::try_from(
+            commit.id().as_bytes()
+        )?)),
+        spacer!(),
+        Node::Capped(
+            commit
+                .message()
+                .unwrap_or_default()
+                .lines()
+                .next()
+                .unwrap_or_default()
+                .to_string()
+                .into()
+        )
+    ))
+}
+
+fn render_stashes(ui: &mut impl Render, repo: &mut Repo) -> Result<(), Box<dyn Error>> {
+    let mut lines = vec![];
+
+    for stash in repo.stashes()? {
+        lines.push(commit_line(&stash?)?);
+    }
+
+    if lines.is_empty() {
+        return Ok(());
+    }
+
+    Ok(ui.renderln(&Node::Group(
+        "Stashes".into(),
+        Some(lines.len()),
+        Box::new(Node::MultiLine(lines)),
+    ))?)
+}
+
+fn render_recent(ui: &mut impl Render, repo: &Repo, count: usize) -> Result<(), Box<dyn Error>> {
+    let mut lines = vec![];
+
+    for commit in repo.commits(&repo.head()?)?.take(count) {
+        lines.push(commit_line(&commit?)?);
+    }
+
+    Ok(ui.renderln(&Node::Group(
+        "Recent Commits".into(),
+        None,
+        Box::new(Node::MultiLine(lines)),
+    ))?)
+}
+
+pub fn run(mut repo: Repo, opts: Opts) -> Result<(), Box<dyn Error>> {
+    let gix_repo = gix::open(repo.path())?;
+    let mut ui = TermRenderer::default().with_width(term::width());
+
+    let state = find_state(&gix_repo)?;
+    let counts = state
+        .as_ref()
+        .map(|(local, remote, _)| Graph::counts(&gix_repo, *local, *remote))
+        .transpose()?;
+    let upstream = state.as_ref().map(|(_, _, name)| name.as_str());
+
+    render_branch(&mut ui, &gix_repo, upstream, counts)?;
+    render_state(&mut ui, &gix_repo)?;
+
+    if !gix_repo.head()?.is_unborn() {
+        render_recent(&mut ui, &repo, opts.max_count)?;
+    }
+
+    render_stashes(&mut ui, &mut repo)
+}
@@ -1,28 +1,298 @@
 use std::{
+    env,
     error::Error,
-    fmt::Write as _,
+    fmt::{self, Write as _},
     io::{stdout, BufRead, BufReader, IsTerminal, Write},
     process::{Command, Stdio},
     thread,
 };
 
 use clap::{Parser, ValueHint};
-use git2::{Diff, DiffFormat};
+use git2::{Delta, Diff, DiffDelta, DiffFormat};
 use minus::Pager;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 use which::which;
 
-use crate::git::{DiffOpts, Pattern, Repo};
+use crate::{
+    git::{pathspec, DiffOpts, Optional, Pattern, Repo, Whitespace},
+    term::{self, node::prelude::*, pager, render::Render},
+};
+
+/// Makes a path field of a file header relative, keeping its `a/`/`b/` prefix.
+fn relative_field(field: &str, full: &str, relative: &str) -> Option<String> {
+    if field == "/dev/null" {
+        return Some(field.to_string());
+    }
+
+    field
+        .strip_suffix(full)
+        .map(|prefix| format!("{prefix}{relative}"))
+}
+
+/// Makes the paths in a file header relative to `prefix`. Only the paths of the delta are replaced
+/// so a directory that repeats further down a path is left alone.
+fn relative_header(header: &str, delta: &DiffDelta, prefix: &str) -> String {
+    let path = |file: git2::DiffFile| {
+        let full = file
+            .path()
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_default();
+        (full, file_path(file, Some(prefix)))
+    };
+    let (old, new) = (path(delta.old_file()), path(delta.new_file()));
+
+    // Paths may contain the separator, split where both sides end with the delta's paths
+    let pair = |fields: &str, separator: &str| {
+        fields
+            .match_indices(separator)
+            .find_map(|(i, _)| {
+                let left = relative_field(&fields[..i], &old.0, &old.1)?;
+                let right = relative_field(&fields[i + separator.len()..], &new.0, &new.1)?;
+                Some(format!("{left}{separator}{right}"))
+            })
+            .unwrap_or_else(|| fields.to_string())
+    };
+
+    header
+        .split_inclusive('\n')
+        .map(|line| {
+            let (text, newline) = line
+                .strip_suffix('\n')
+                .map_or((line, ""), |text| (text, "\n"));
+            let field = |keys: [&str; 3], (full, relative): &(String, String)| {
+                keys.iter().find_map(|key| {
+                    let field = text.strip_prefix(key)?;
+                    Some(format!("{key}{}", relative_field(field, full, relative)?))
+                })
+            };
+
+            let text = if let Some(fields) = text.strip_prefix("diff --git ") {
+                format!("diff --git {}", pair(fields, " "))
+            } else if let Some(fields) = text
+                .strip_prefix("Binary files ")
+                .and_then(|text| text.strip_suffix(" differ"))
+            {
+                format!("Binary files {} differ", pair(fields, " and "))
+            } else {
+                field(["--- ", "rename from ", "copy from "], &old)
+                    .or_else(|| field(["+++ ", "rename to ", "copy to "], &new))
+                    .unwrap_or_else(|| text.to_string())
+            };
+
+            format!("{text}{newline}")
+        })
+        .collect()
+}
+
+fn status_letter(status: Delta) -> char {
+    match status {
+        Delta::Added => 'A',
+        Delta::Deleted => 'D',
+        Delta::Modified => 'M',
+        Delta::Renamed => 'R',
+        Delta::Copied => 'C',
+        Delta::Typechange => 'T',
+        Delta::Untracked => '?',
+        Delta::Conflicted => 'U',
+        _ => 'X',
+    }
+}
+
+/// Path of the file, relative to the `relative` directory when given.
+fn file_path(file: git2::DiffFile, relative: Option<&str>) -> String {
+    let path = file
+        .path()
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    relative
+        .and_then(|prefix| path.strip_prefix(&format!("{prefix}/")))
+        .map(ToString::to_string)
+        .unwrap_or(path)
+}
+
+fn render_names(diff: &Diff, with_status: bool, relative: Option<&str>) -> Vec<String> {
+    let path = |file| file_path(file, relative);
+
+    diff.deltas()
+        .map(|delta| {
+            let new = path(delta.new_file());
+
+            if !with_status {
+                return new;
+            }
+
+            match delta.status() {
+                Delta::Renamed | Delta::Copied => format!(
+                    "{}\t{}\t{new}",
+                    status_letter(delta.status()),
+                    path(delta.old_file())
+                ),
+                status => format!("{}\t{new}", status_letter(status)),
+            }
+        })
+        .collect()
+}
+
+fn plural(count: usize, word: &str) -> String {
+    match count {
+        1 => format!("{count} {word}"),
+        _ => format!("{count} {word}s"),
+    }
+}
+
+/// Shortens a path to `width` columns by replacing its start with `...`, like git.
+fn shorten_path(path: &str, width: usize) -> String {
+    if path.width() <= width {
+        return path.to_string();
+    }
+
+    let mut tail = vec![];
+    let mut used = "...".len();
+
+    for c in path.chars().rev() {
+        used += c.width().unwrap_or(0);
+
+        if used > width {
+            break;
+        }
+
+        tail.push(c);
+    }
+
+    format!("...{}", tail.into_iter().rev().collect::<String>())
+}
+
+/// Splits the bar of a file between insertions and deletions. The total is scaled once so the
+/// bar never exceeds `width`, each non-zero side keeps at least one column like git.
+fn scale_bar(added: usize, removed: usize, most: usize, width: usize) -> (usize, usize) {
+    let total = added + removed;
+
+    if most <= width || total == 0 {
+        return (added, removed);
+    }
+
+    let scale = |n: usize, width: usize, max: usize| match n {
+        0 => 0,
+        n => 1 + n * (width.max(1) - 1) / max,
+    };
+    let mut bar = scale(total, width, most);
 
-fn render_diff(diff: &Diff) -> Result<Vec<u8>, git2::Error> {
+    if bar < 2 && added > 0 && removed > 0 {
+        bar = 2;
+    }
+
+    if added < removed {
+        let plus = scale(added, bar, total);
+        (plus, bar - plus)
+    } else {
+        let minus = scale(removed, bar, total);
+        (bar - minus, minus)
+    }
+}
+
+/// Renders the diffstat like `git diff --stat`, the paths are relative to the `relative`
+/// directory when given and the bars are scaled down to fit `width`.
+fn render_stat(diff: &Diff, relative: Option<&str>, width: usize) -> Result<String, git2::Error> {
+    let mut files = vec![];
+
+    for (idx, delta) in diff.deltas().enumerate() {
+        let path = match delta.status() {
+            Delta::Renamed | Delta::Copied => format!(
+                "{} => {}",
+                file_path(delta.old_file(), relative),
+                file_path(delta.new_file(), relative)
+            ),
+            _ => file_path(delta.new_file(), relative),
+        };
+        let changes = match git2::Patch::from_diff(diff, idx)? {
+            Some(patch) if !delta.flags().is_binary() => {
+                let (_, insertions, deletions) = patch.line_stats()?;
+                Some((insertions, deletions))
+            }
+            _ => None,
+        };
+
+        files.push((path, changes));
+    }
+
+    let most = files
+        .iter()
+        .filter_map(|(_, changes)| changes.map(|(i, d)| i + d))
+        .max()
+        .unwrap_or(0);
+    let count_width = files
+        .iter()
+        .map(|(_, changes)| match changes {
+            Some((insertions, deletions)) => (insertions + deletions).to_string().len(),
+            None => "Bin".len(),
+        })
+        .max()
+        .unwrap_or(0);
+    // Long paths are shortened so the line fits and there's still room for a short bar
+    let available = width.saturating_sub(count_width + 6);
+    let name_width = files
+        .iter()
+        .map(|(path, _)| path.width())
+        .max()
+        .unwrap_or(0)
+        .min(available.saturating_sub(most.min(10)));
+    let bar_width = available - name_width;
+
+    let mut output = String::new();
+    let (mut insertions, mut deletions) = (0, 0);
+
+    for (path, changes) in &files {
+        let path = shorten_path(path, name_width);
+        let padding = " ".repeat(name_width.saturating_sub(path.width()));
+
+        let _ = match changes {
+            Some((added, removed)) => {
+                insertions += added;
+                deletions += removed;
+
+                let (plus, minus) = scale_bar(*added, *removed, most, bar_width);
+                writeln!(
+                    output,
+                    " {path}{padding} | {:>count_width$} {}{}",
+                    added + removed,
+                    "+".repeat(plus),
+                    "-".repeat(minus)
+                )
+            }
+            None => writeln!(output, " {path}{padding} | {:>count_width$}", "Bin"),
+        };
+    }
+
+    let mut summary = vec![format!("{} changed", plural(files.len(), "file"))];
+
+    if insertions > 0 {
+        summary.push(format!("{}(+)", plural(insertions, "insertion")));
+    }
+
+    if deletions > 0 {
+        summary.push(format!("{}(-)", plural(deletions, "deletion")));
+    }
+
+    let _ = writeln!(output, " {}", summary.join(", "));
+
+    Ok(output)
+}
+
+pub fn render_diff(diff: &Diff, relative: Option<&str>) -> Result<Vec<u8>, git2::Error> {
     let mut output = vec![];
 
-    diff.print(DiffFormat::Patch, |_delta, _hunk, line| {
+    diff.print(DiffFormat::Patch, |delta, _hunk, line| {
         let content = std::str::from_utf8(line.content()).unwrap_or_default();
         let _ = match line.origin() {
             '+' => write!(output, "+{content}"),
             '-' => write!(output, "-{content}"),
             ' ' => write!(output, " {content}"),
-            'F' | 'H' => write!(output, "{content}"),
+            'F' | 'B' => match relative {
+                Some(prefix) => write!(output, "{}", relative_header(content, &delta, prefix)),
+                None => write!(output, "{content}"),
+            },
+            'H' => write!(output, "{content}"),
             _ => write!(output, "{content}"),
         };
 
@@ -32,14 +302,47 @@ fn render_diff(diff: &Diff) -> Result<Vec<u8>, git2::Error> {
     Ok(output)
 }
 
+/// Renders the diffstat followed by the patch, like `git diff --stat --patch`.
+fn render_stat_patch(
+    diff: &Diff,
+    relative: Option<&str>,
+    width: usize,
+) -> Result<Vec<u8>, git2::Error> {
+    let mut output = render_stat(diff, relative, width)?.into_bytes();
+    output.push(b'\n');
+    output.extend(render_diff(diff, relative)?);
+
+    Ok(output)
+}
+
+/// Renders a patch with added and removed lines highlighted.
+pub fn render_colored(ui: &mut impl Render, patch: &[u8]) -> fmt::Result {
+    for line in String::from_utf8_lossy(patch).lines() {
+        let node = text!(line.to_string());
+
+        ui.renderln(&match line.chars().next() {
+            _ if line.starts_with("+++") || line.starts_with("---") => node,
+            Some('+') => node.with_status(Status::Success),
+            Some('-') => node.with_status(Status::Error),
+            Some('@') => dimmed!(node),
+            _ => node,
+        })?;
+    }
+
+    Ok(())
+}
+
 #[derive(Parser)]
 #[clap(about = "Show changes")]
 pub struct Opts {
     #[clap(short, long, default_value = "false")]
     pub patch: bool,
 
-    #[clap(value_hint = ValueHint::AnyPath)]
-    pub filter: Option<String>,
+    #[clap(
+        value_hint = ValueHint::AnyPath,
+        help = "Commit to compare against and/or pathspecs (e.g. ':!*.lock')"
+    )]
+    pub filter: Vec<String>,
 
     #[clap(long, help = "Disable the pager")]
     no_pager: bool,
@@ -49,11 +352,112 @@ pub struct Opts {
 
     #[clap(short, long)]
     pub all: bool,
+
+    #[clap(
+        short = 'U',
+        long,
+        value_name = "N",
+        help = "Number of context lines to show"
+    )]
+    pub unified: Option<u32>,
+
+    #[clap(
+        long,
+        conflicts_with = "name_status",
+        help = "Only show the names of changed files"
+    )]
+    pub name_only: bool,
+
+    #[clap(long, help = "Only show the names and status of changed files")]
+    pub name_status: bool,
+
+    #[clap(
+        long,
+        help = "Show a diffstat (before the patch when combined with --patch)"
+    )]
+    pub stat: bool,
+
+    #[clap(
+        long,
+        value_name = "DIR",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "",
+        help = "Show paths relative to the current (or given) directory"
+    )]
+    pub relative: Option<String>,
+
+    #[clap(
+        long,
+        help = "Don't show the a/ and b/ prefixes (defaults to diff.noprefix)"
+    )]
+    pub no_prefix: bool,
+
+    #[clap(
+        short = 'w',
+        long,
+        conflicts_with = "no_ignore_whitespace",
+        help = "Ignore whitespace when comparing lines (default)"
+    )]
+    pub ignore_all_space: bool,
+
+    #[clap(long, help = "Show whitespace-only changes")]
+    pub no_ignore_whitespace: bool,
+}
+
+fn relative_prefix(repo: &Repo, relative: &str) -> Result<Option<String>, Box<dyn Error>> {
+    let prefix = if relative.is_empty() {
+        let workdir = repo
+            .workdir()
+            .ok_or("no working directory")?
+            .canonicalize()?;
+        let cwd = env::current_dir()?.canonicalize()?;
+
+        cwd.strip_prefix(workdir)?
+            .to_str()
+            .ok_or("invalid path")?
+            .to_string()
+    } else {
+        relative.to_string()
+    };
+    let prefix = prefix.trim_matches('/');
+
+    if prefix.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(prefix.to_string()))
+}
+
+fn split_filter<'a>(
+    repo: &Repo,
+    filter: &'a [String],
+) -> Result<(Option<git2::Oid>, &'a [String]), git2::Error> {
+    let Some((first, rest)) = filter.split_first() else {
+        return Ok((None, filter));
+    };
+
+    // Pathspecs with magic (such as `:!*.lock`) are never a commit
+    if first.starts_with(':') {
+        return Ok((None, filter));
+    }
+
+    match Pattern::parse(first) {
+        Ok((_, pat)) => match pat.resolve(repo)? {
+            Some(oid) => Ok((Some(oid), rest)),
+            None => Ok((None, filter)),
+        },
+        Err(_) => Ok((None, filter)),
+    }
 }
 
 pub fn run(repo: Repo, opts: Opts) -> Result<(), Box<dyn Error>> {
     let head = repo.head()?;
     let tree = head.find_tree()?;
+    let (target, pathspecs) = split_filter(&repo, &opts.filter)?;
+    let target = target
+        .map(|oid| repo.find_commit(oid).and_then(|commit| commit.find_tree()))
+        .transpose()?;
     let mut diff_opts = DiffOpts::default();
 
     if opts.staged {
@@ -64,55 +468,160 @@ pub fn run(repo: Repo, opts: Opts) -> Result<(), Box<dyn Error>> {
         diff_opts = diff_opts.with_all(&tree);
     }
 
-    let diff = if let Some(ref filter) = opts.filter {
-        if let Ok((_, pat)) = Pattern::parse(filter) {
-            if let Some(oid) = pat.resolve(&repo)? {
-                let commit = repo.find_commit(oid)?;
-                let tree = commit.find_tree()?;
+    if let Some(tree) = target.as_ref() {
+        diff_opts = diff_opts.with_all(tree);
+    }
 
-                repo.diff(diff_opts.with_all(&tree))?
-            } else {
-                repo.diff(diff_opts.with_pathspec(filter))?
-            }
-        } else {
-            repo.diff(diff_opts.with_pathspec(filter))?
+    if opts.no_prefix
+        || repo
+            .config()?
+            .get_bool("diff.noprefix")
+            .optional()?
+            .unwrap_or(false)
+    {
+        diff_opts = diff_opts.with_no_prefix();
+    }
+
+    if opts.no_ignore_whitespace {
+        diff_opts = diff_opts.with_whitespace(Whitespace::Exact);
+    } else if opts.ignore_all_space {
+        diff_opts = diff_opts.with_whitespace(Whitespace::IgnoreAll);
+    }
+
+    if let Some(lines) = opts.unified {
+        diff_opts = diff_opts.with_context(lines);
+    }
+
+    let relative = match opts.relative.as_deref() {
+        Some(relative) => relative_prefix(&repo, relative)?,
+        None => None,
+    };
+
+    if let Some(prefix) = relative.as_deref() {
+        diff_opts = diff_opts.with_pathspec(pathspec::with_prefix(pathspecs, prefix));
+    } else if !pathspecs.is_empty() {
+        diff_opts = diff_opts.with_pathspec(pathspecs);
+    }
+
+    let diff = repo.diff(diff_opts)?;
+
+    if opts.name_only || opts.name_status {
+        for line in render_names(&diff, opts.name_status, relative.as_deref()) {
+            println!("{line}");
         }
-    } else {
-        repo.diff(diff_opts)?
+
+        return Ok(());
+    }
+
+    if opts.stat {
+        if !opts.patch {
+            print!(
+                "{}",
+                render_stat(&diff, relative.as_deref(), term::width())?
+            );
+            return Ok(());
+        }
+
+        let output = render_stat_patch(&diff, relative.as_deref(), term::width())?;
+        return display(&repo, output, String::new(), true, opts.no_pager);
+    }
+
+    display(
+        &repo,
+        render_diff(&diff, relative.as_deref())?,
+        if opts.filter.is_empty() {
+            "HEAD".to_string()
+        } else {
+            opts.filter.join(" ")
+        },
+        opts.patch,
+        opts.no_pager,
+    )
+}
+
+/// Finds a tool to highlight the diff with, `delta` is preferred over `bat`. There is none when
+/// colors are disabled (`--color never`, `NO_COLOR` or stdout isn't a terminal).
+fn highlighter() -> Option<Command> {
+    if !colored::control::SHOULD_COLORIZE.should_colorize() {
+        return None;
+    }
+
+    if let Ok(path) = which("delta") {
+        return Some(Command::new(path));
+    }
+
+    which("bat").ok().map(|path| {
+        let mut cmd = Command::new(path);
+        cmd.args(["--language=diff", "--color=always", "--paging=never"]);
+        cmd
+    })
+}
+
+/// Runs the diff through the highlighter, returns it unchanged if there is none.
+fn highlight(diff: Vec<u8>) -> Result<Vec<u8>, Box<dyn Error>> {
+    let Some(mut cmd) = highlighter() else {
+        return Ok(diff);
     };
 
-    if opts.patch {
-        println!("{}", String::from_utf8(render_diff(&diff)?)?);
+    let mut child = cmd
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()?;
+    let mut stdin = child.stdin.take().unwrap();
+
+    // Write from another thread as the highlighter blocks when its stdout isn't read
+    let writer = thread::spawn(move || stdin.write_all(&diff));
+    let output = child.wait_with_output()?;
+    writer.join().map_err(|_| "highlighter panicked")??;
+
+    Ok(output.stdout)
+}
+
+pub fn display(
+    repo: &Repo,
+    diff: Vec<u8>,
+    title: String,
+    patch: bool,
+    no_pager: bool,
+) -> Result<(), Box<dyn Error>> {
+    // Plumbing mode always emits the raw patch
+    if patch || term::plumbing() {
+        println!("{}", String::from_utf8(diff)?);
+        return Ok(());
+    }
+
+    if no_pager {
+        println!("{}", String::from_utf8(highlight(diff)?)?);
         return Ok(());
     }
 
-    match which("delta") {
-        Ok(path) => {
-            let mut child = Command::new(path)
+    if let Some(command) = pager::configured(repo.config()?.get_string("core.pager").optional()?) {
+        // Pagers like delta highlight the diff themselves
+        let program = command.split_whitespace().next().unwrap_or_default();
+        let diff = if ["delta", "bat"].iter().any(|tool| program.ends_with(tool)) {
+            diff
+        } else {
+            highlight(diff)?
+        };
+        let diff = String::from_utf8(diff)?;
+
+        return term::pager::Pager::new(Some(&command), title)?
+            .page_all(|pager| Ok(pager.write_str(&diff)?));
+    }
+
+    match highlighter() {
+        Some(mut cmd) => {
+            let mut child = cmd
                 .stdin(Stdio::piped())
                 .stdout(Stdio::piped())
                 .stderr(Stdio::inherit())
                 .spawn()?;
 
-            if opts.no_pager {
-                let stdin = child.stdin.as_mut().unwrap();
-                stdin.write_all(&render_diff(&diff)?)?;
-                stdin.flush()?;
-
-                let output = child.wait_with_output()?;
-                println!("{}", String::from_utf8(output.stdout)?);
-
-                return Ok(());
-            }
-
             let mut pager = Pager::new();
-            pager.set_prompt(format!(
-                "diff {}, q to quit",
-                opts.filter.as_deref().unwrap_or("HEAD")
-            ))?;
+            pager.set_prompt(format!("diff {title}, q to quit"))?;
 
             let mut stdin = child.stdin.take().unwrap();
-            let diff = render_diff(&diff)?;
 
             thread::spawn(move || {
                 stdin.write_all(&diff)?;
@@ -142,8 +651,91 @@ pub fn run(repo: Repo, opts: Opts) -> Result<(), Box<dyn Error>> {
                 minus::page_all(pager)?;
             }
         }
-        Err(_) => println!("{}", String::from_utf8(render_diff(&diff)?)?),
+        None => println!("{}", String::from_utf8(diff)?),
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::fixture::{self, commit_file};
+
+    #[test]
+    fn test_relative_stat() {
+        let (dir, git) = fixture::init();
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+        commit_file(&git, "sub/a", "1\n2\n", "add a", &fixture::signature());
+
+        std::fs::write(dir.path().join("sub/a"), "1\n3\n4\n").unwrap();
+        std::fs::write(dir.path().join("sub/c"), "c\n").unwrap();
+
+        let repo = Repo::from(git);
+        let diff = repo
+            .diff(DiffOpts::default().with_pathspec(["sub"]))
+            .unwrap();
+
+        assert_eq!(
+            render_stat(&diff, Some("sub"), 80).unwrap(),
+            " a | 3 ++-\n c | 1 +\n 2 files changed, 3 insertions(+), 1 deletion(-)\n"
+        );
+    }
+
+    #[test]
+    fn test_relative_patch() {
+        let (dir, git) = fixture::init();
+        std::fs::create_dir_all(dir.path().join("src/a/src")).unwrap();
+        commit_file(
+            &git,
+            "src/a/src/b.rs",
+            "1\n",
+            "add b",
+            &fixture::signature(),
+        );
+        std::fs::write(dir.path().join("src/a/src/b.rs"), "2\n").unwrap();
+
+        let repo = Repo::from(git);
+        let diff = repo
+            .diff(DiffOpts::default().with_pathspec(["src"]))
+            .unwrap();
+        let patch = String::from_utf8(render_diff(&diff, Some("src")).unwrap()).unwrap();
+
+        assert!(patch.starts_with("diff --git a/a/src/b.rs b/a/src/b.rs\nindex "));
+        assert!(patch.contains("\n--- a/a/src/b.rs\n+++ b/a/src/b.rs\n"));
+    }
+
+    #[test]
+    fn test_stat_scaling() {
+        // The total is scaled once, both sides keep at least one column
+        assert_eq!(scale_bar(50, 50, 100, 10), (5, 5));
+        assert_eq!(scale_bar(99, 1, 100, 10), (9, 1));
+        assert_eq!(scale_bar(1, 1, 1000, 10), (1, 1));
+        assert_eq!(scale_bar(3, 0, 1000, 10), (1, 0));
+        assert_eq!(scale_bar(2, 1, 10, 80), (2, 1));
+
+        for (added, removed) in [(7, 3), (1, 9), (333, 667), (1000, 0)] {
+            let (plus, minus) = scale_bar(added, removed, 1000, 7);
+            assert!(plus + minus <= 7);
+        }
+
+        assert_eq!(shorten_path("src/main.rs", 20), "src/main.rs");
+        assert_eq!(shorten_path("src/main.rs", 10), "...main.rs");
+        assert_eq!(shorten_path("文書/説明.md", 10), "...説明.md");
+    }
+
+    #[test]
+    fn test_stat_patch() {
+        let (dir, git) = fixture::init();
+        commit_file(&git, "a", "1\n", "add a", &fixture::signature());
+        std::fs::write(dir.path().join("a"), "2\n").unwrap();
+
+        let repo = Repo::from(git);
+        let diff = repo.diff(DiffOpts::default()).unwrap();
+        let output = String::from_utf8(render_stat_patch(&diff, None, 80).unwrap()).unwrap();
+
+        assert!(output.starts_with(
+            " a | 2 +-\n 1 file changed, 1 insertion(+), 1 deletion(-)\n\ndiff --git a/a b/a\n"
+        ));
+    }
+}
@@ -1,14 +1,76 @@
 use std::error::Error;
 
 use clap::Parser;
+use gix::ObjectId;
 
-use crate::git::{RemoteOpts, Repo};
+use crate::{
+    cmd::push::render_reply,
+    git::{RemoteOpts, Repo, Update},
+    term::{
+        node::prelude::*,
+        render::{Render, TermRenderer},
+        setup_progress, ProgressFormat,
+    },
+};
 
 #[derive(Parser)]
 #[clap(about = "Download objects and refs")]
-pub struct Opts {}
+pub struct Opts {
+    #[clap(
+        long,
+        value_enum,
+        default_value = "bar",
+        help = "Progress output format (json prints NDJSON events to stderr)"
+    )]
+    progress: ProgressFormat,
+}
+
+fn render_summary(ui: &mut impl Render, updates: &[Update]) -> Result<(), Box<dyn Error>> {
+    if updates.is_empty() {
+        return Ok(ui.renderln(&message_with_icon(Icon::Check, "up to date"))?);
+    }
+
+    let (mut created, mut updated, mut deleted) = (0, 0, 0);
+    let mut lines = vec![];
+
+    for update in updates {
+        let tip = if update.dst.is_zero() {
+            deleted += 1;
+            dimmed!(text!("(deleted)"))
+        } else {
+            if update.src.is_zero() {
+                created += 1;
+            } else {
+                updated += 1;
+            }
+
+            Node::Attribute(Attribute::CommitShort(ObjectId::try_from(
+                update.dst.as_bytes(),
+            )?))
+        };
+
+        lines.push(block!(
+            spacer!(),
+            spacer!(),
+            text!(update.refname.clone()),
+            spacer!(),
+            tip
+        ));
+    }
 
-pub fn run(repo: Repo, _opts: Opts) -> Result<(), Box<dyn Error>> {
+    ui.renderln(&message_with_icon(
+        Icon::Check,
+        format!("{updated} updated, {created} created, {deleted} deleted"),
+    ))?;
+
+    Ok(ui.renderln(&Node::Group(
+        "Fetched".into(),
+        Some(lines.len()),
+        Box::new(Node::MultiLine(lines)),
+    ))?)
+}
+
+pub fn run(repo: Repo, opts: Opts) -> Result<(), Box<dyn Error>> {
     let head = repo.head()?;
     let branch = head.shorthand()?;
 
@@ -16,8 +78,17 @@ pub fn run(repo: Repo, _opts: Opts) -> Result<(), Box<dyn Error>> {
     let upstream = branch.upstream()?;
     let remote = upstream.remote_name()?;
 
+    let (tx, rx) = std::sync::mpsc::channel();
+    let handle = setup_progress(rx, opts.progress);
+
     let mut remote = repo.find_remote(remote)?;
-    remote.fetch(RemoteOpts::default(), branch.name()?)?;
+    let reply = remote.fetch(RemoteOpts::default().with_progress(tx), branch.name()?)?;
+
+    let _ = handle.join();
+
+    let mut ui = TermRenderer::default();
+    render_summary(&mut ui, &reply.updates)?;
+    render_reply(&reply.stdout);
 
     Ok(())
 }
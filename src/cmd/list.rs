@@ -1,13 +1,18 @@
 use std::error::Error;
 
 use clap::Parser;
+use git2::{Diff, DiffStatsFormat};
 use gix::ObjectId;
-use minus::Pager;
+use regex::{Regex, RegexBuilder};
 
 use crate::{
-    git::{Commit, Repo},
+    cmd::status::ahead_behind_indicators,
+    git::{parse_date, Commit, DateFormat, Optional, Repo},
+    graph::Lanes,
     term::{
+        self,
         node::prelude::*,
+        pager::{self, Pager},
         render::{Render, TermRenderer},
     },
 };
@@ -21,6 +26,57 @@ pub struct Opts {
     #[clap(long, help = "Disable the pager")]
     no_pager: bool,
 
+    #[clap(short, long, help = "Show the changes introduced by each commit")]
+    patch: bool,
+
+    #[clap(long, help = "Show a diffstat for each commit (not with --short)")]
+    stat: bool,
+
+    #[clap(
+        long,
+        value_enum,
+        default_value = "local",
+        help = "Date format to show"
+    )]
+    date: DateFormat,
+
+    #[clap(long, help = "Draw the commit graph next to the commits")]
+    graph: bool,
+
+    #[clap(
+        long,
+        conflicts_with_all = ["short", "graph"],
+        help = "Format commits with placeholders: %H, %h, %an, %ae, %ad, %s, %b, %n and %%"
+    )]
+    format: Option<String>,
+
+    #[clap(short = 'n', long, help = "Limit the number of commits to show")]
+    max_count: Option<usize>,
+
+    #[clap(
+        long,
+        help = "Show commits more recent than a date (e.g. 2024-01-31 or 2.weeks.ago)"
+    )]
+    since: Option<String>,
+
+    #[clap(long, help = "Show commits older than a date")]
+    until: Option<String>,
+
+    #[clap(
+        long,
+        help = "Show commits with an author name or email matching the pattern"
+    )]
+    author: Option<String>,
+
+    #[clap(
+        long,
+        help = "Show commits with a message matching the pattern (case-insensitive)"
+    )]
+    grep: Option<String>,
+
+    #[clap(long, requires = "grep", help = "Match --grep case-sensitively")]
+    case_sensitive: bool,
+
     #[clap(subcommand)]
     cmd: Option<Cmd>,
 }
@@ -34,6 +90,9 @@ pub enum Cmd {
     Commit {
         #[clap(help = "Target branch or tag")]
         target: Option<String>,
+
+        #[clap(last = true, help = "Only show commits changing these paths")]
+        paths: Vec<String>,
     },
 
     #[clap(about = "List remotes")]
@@ -69,14 +128,234 @@ fn list_remotes(ui: &mut impl Render, repo: &mut Repo) -> Result<(), Box<dyn Err
     Ok(())
 }
 
+struct Filter {
+    since: Option<i64>,
+    until: Option<i64>,
+    author: Option<Regex>,
+    grep: Option<Regex>,
+}
+
+impl Filter {
+    fn from_opts(opts: &Opts) -> Result<Self, Box<dyn Error>> {
+        let date = |date: &Option<String>| {
+            date.as_deref()
+                .map(|date| {
+                    parse_date(date)
+                        .map(|time| time.seconds())
+                        .ok_or_else(|| format!("invalid date: {date}"))
+                })
+                .transpose()
+        };
+
+        Ok(Self {
+            since: date(&opts.since)?,
+            until: date(&opts.until)?,
+            author: opts.author.as_deref().map(Regex::new).transpose()?,
+            grep: opts
+                .grep
+                .as_deref()
+                .map(|pattern| {
+                    RegexBuilder::new(pattern)
+                        .case_insensitive(!opts.case_sensitive)
+                        .build()
+                })
+                .transpose()?,
+        })
+    }
+
+    fn matches(&self, commit: &Commit<'_>) -> bool {
+        let time = commit.time().seconds();
+
+        if self.since.is_some_and(|since| time < since)
+            || self.until.is_some_and(|until| time > until)
+        {
+            return false;
+        }
+
+        self.author
+            .as_ref()
+            .is_none_or(|re| re.is_match(&commit.author().to_string()))
+            && self
+                .grep
+                .as_ref()
+                .is_none_or(|re| re.is_match(commit.message().unwrap_or_default()))
+    }
+}
+
+fn render_stat(ui: &mut impl Render, diff: &Diff) -> Result<(), Box<dyn Error>> {
+    let buf = diff.stats()?.to_buf(DiffStatsFormat::FULL, term::width())?;
+    let stat = String::from_utf8_lossy(&buf).trim_end().to_string();
+
+    Ok(ui.renderln(&text!(stat))?)
+}
+
+fn render_patch(ui: &mut impl Render, diff: &Diff) -> Result<(), Box<dyn Error>> {
+    let patch = super::diff::render_diff(diff, None)?;
+
+    super::diff::render_colored(ui, &patch)?;
+    Ok(ui.renderln(&Node::Empty)?)
+}
+
+enum Token {
+    Text(String),
+    Hash,
+    ShortHash,
+    AuthorName,
+    AuthorEmail,
+    AuthorDate,
+    Subject,
+    Body,
+}
+
+/// Parses a `--format` string like `%h %s (%an)` into tokens.
+fn parse_format(format: &str) -> Result<Vec<Token>, Box<dyn Error>> {
+    let mut tokens = vec![];
+    let mut text = String::new();
+    let mut chars = format.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            text.push(c);
+            continue;
+        }
+
+        let token = match chars.next() {
+            Some('%') => {
+                text.push('%');
+                continue;
+            }
+            Some('n') => {
+                text.push('\n');
+                continue;
+            }
+            Some('H') => Token::Hash,
+            Some('h') => Token::ShortHash,
+            Some('s') => Token::Subject,
+            Some('b') => Token::Body,
+            Some('a') => match chars.next() {
+                Some('n') => Token::AuthorName,
+                Some('e') => Token::AuthorEmail,
+                Some('d') => Token::AuthorDate,
+                Some(c) => return Err(format!("unknown placeholder in format: %a{c}").into()),
+                None => return Err("incomplete placeholder in format: %a".into()),
+            },
+            Some(c) => return Err(format!("unknown placeholder in format: %{c}").into()),
+            None => return Err("incomplete placeholder in format: %".into()),
+        };
+
+        if !text.is_empty() {
+            tokens.push(Token::Text(std::mem::take(&mut text)));
+        }
+
+        tokens.push(token);
+    }
+
+    if !text.is_empty() {
+        tokens.push(Token::Text(text));
+    }
+
+    Ok(tokens)
+}
+
+fn format_commit(
+    tokens: &[Token],
+    commit: &Commit<'_>,
+    date: DateFormat,
+) -> Result<String, Box<dyn Error>> {
+    let mut output = String::new();
+    let author = commit.author();
+
+    for token in tokens {
+        match token {
+            Token::Text(text) => output.push_str(text),
+            Token::Hash => output.push_str(&commit.id().to_string()),
+            Token::ShortHash => output.push_str(&commit.id().to_string()[..7]),
+            Token::AuthorName => output.push_str(author.name().unwrap_or_default()),
+            Token::AuthorEmail => output.push_str(author.email().unwrap_or_default()),
+            Token::AuthorDate => output.push_str(&date.format(author.when())),
+            // Like git, a subject spanning several lines is joined into one
+            Token::Subject => output.push_str(
+                &commit
+                    .subject()?
+                    .lines()
+                    .map(str::trim)
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            ),
+            Token::Body => output.push_str(commit.body()?),
+        }
+    }
+
+    Ok(output)
+}
+
+fn render_body(
+    ui: &mut impl Render,
+    commit: &Commit<'_>,
+    opts: &Opts,
+    repo: Option<&Repo>,
+) -> Result<(), Box<dyn Error>> {
+    if !opts.short {
+        ui.renderln(&multi_line!(
+            dimmed!(commit.headers_ui(opts.date)),
+            spacer!(),
+            text!(commit.message_formatted()),
+            Node::Empty
+        ))?;
+    }
+
+    // Diffs are computed per commit so they stream into the (dynamic) pager
+    if let Some(repo) = repo {
+        let diff = repo.commit_diff(commit)?;
+
+        if opts.stat {
+            render_stat(ui, &diff)?;
+        }
+
+        if opts.patch {
+            render_patch(ui, &diff)?;
+        }
+    }
+
+    Ok(())
+}
+
 fn list_commits<'a>(
     ui: &mut impl Render,
     walk: impl Iterator<Item = Result<Commit<'a>, git2::Error>>,
-    short: bool,
+    opts: &Opts,
+    repo: Option<&Repo>,
 ) -> Result<(), Box<dyn Error>> {
-    for commit in walk {
+    let short = opts.short;
+    // Stashes aren't connected by their parents, so they can't be drawn as a graph
+    let mut lanes = (opts.graph && !matches!(opts.cmd, Some(Cmd::Stash))).then(Lanes::default);
+
+    let filter = Filter::from_opts(opts)?;
+    let walk = walk.filter(|commit| {
+        commit
+            .as_ref()
+            .map_or(true, |commit| filter.matches(commit))
+    });
+
+    let format = opts.format.as_deref().map(parse_format).transpose()?;
+
+    for commit in walk.take(opts.max_count.unwrap_or(usize::MAX)) {
         let commit = commit?;
 
+        if let Some(tokens) = &format {
+            ui.renderln(&text!(format_commit(tokens, &commit, opts.date)?))?;
+            continue;
+        }
+
+        let rows = match &mut lanes {
+            Some(lanes) => lanes.next(&commit.id(), &commit.parent_ids()),
+            None => vec![],
+        };
+
+        if let Some(row) = rows.first() {
+            ui.render(&text!(format!("{row} ")))?;
+        }
+
         if commit.is_signed() {
             ui.render(&block!(icon!(Lock).with_status(Status::Success), spacer!()))?;
         } else if short {
@@ -90,15 +369,32 @@ fn list_commits<'a>(
         let message = commit.message().unwrap_or_default().trim();
 
         if short {
-            ui.renderln(&Node::text_head_1(message))?;
+            let title = message.lines().next().unwrap_or_default().to_string();
+            ui.renderln(&Node::Capped(title.into()))?;
         } else {
-            ui.renderln(&multi_line!(
-                Node::Empty,
-                dimmed!(commit.headers_ui()),
-                spacer!(),
-                text!(commit.message_formatted()),
-                Node::Empty
-            ))?;
+            ui.renderln(&Node::Empty)?;
+        }
+
+        for row in rows.iter().skip(1) {
+            ui.renderln(&text!(row.to_string()))?;
+        }
+
+        let Some(lanes) = &lanes else {
+            render_body(ui, &commit, opts, repo)?;
+            continue;
+        };
+
+        // Render the body separately so each line can be prefixed with the lanes
+        let mut body = TermRenderer::new(String::new());
+        render_body(&mut body, &commit, opts, repo)?;
+
+        let padding = lanes.padding();
+        let width = rows.first().map(|row| row.chars().count()).unwrap_or(0);
+
+        for line in body.into_inner().lines() {
+            ui.renderln(&text!(format!("{padding:<width$} {line}")
+                .trim_end()
+                .to_string()))?;
         }
     }
 
@@ -106,47 +402,203 @@ fn list_commits<'a>(
 }
 
 fn list_branches(ui: &mut impl Render, repo: Repo) -> Result<(), Box<dyn Error>> {
-    for branch in repo.branches()? {
-        ui.renderln(&Node::Attribute(Attribute::Branch(
-            branch?.name()?.to_string().into(),
-        )))?;
+    let mut branches = repo.branches()?.collect::<Result<Vec<_>, _>>()?;
+    // The current branch is listed first
+    branches.sort_by_key(|branch| !branch.0.is_head());
+
+    for branch in branches {
+        let marker = if branch.0.is_head() {
+            text!("* ").with_status(Status::Success)
+        } else {
+            text!("  ")
+        };
+        let name = block!(
+            marker,
+            Node::Attribute(Attribute::Branch(branch.name()?.to_string().into()))
+        );
+        let indicators = match branch.upstream().optional()? {
+            Some(upstream) => {
+                let (ahead, behind) = repo.ahead_behind(branch.target()?, upstream.target()?)?;
+                ahead_behind_indicators(ahead, behind)
+            }
+            None => None,
+        };
+
+        match indicators {
+            Some(indicators) => ui.renderln(&block!(name, spacer!(), indicators))?,
+            None => ui.renderln(&name)?,
+        }
     }
 
     Ok(())
 }
 
 fn render(mut ui: impl Render, mut repo: Repo, opts: Opts) -> Result<(), Box<dyn Error>> {
-    match opts.cmd {
+    match &opts.cmd {
         Some(cmd) => match cmd {
             Cmd::Branch => list_branches(&mut ui, repo),
             Cmd::Remote => list_remotes(&mut ui, &mut repo),
-            Cmd::Stash => list_commits(&mut ui, repo.stashes()?, opts.short),
-            Cmd::Commit { target } => {
+            Cmd::Stash => list_commits(&mut ui, repo.stashes()?, &opts, None),
+            Cmd::Commit { target, paths } => {
                 let target = match target {
-                    Some(target) => repo.find_branch(&target).map(|b| b.into_ref()),
+                    Some(target) => repo.find_branch(target).map(|b| b.into_ref()),
                     None => repo.head(),
                 }?;
+                let diffs = (opts.patch || opts.stat).then_some(&repo);
+                let walk = repo.commits(&target)?.filter_map(|commit| match commit {
+                    Ok(commit) if !paths.is_empty() => match repo.touches(&commit, paths) {
+                        Ok(true) => Some(Ok(commit)),
+                        Ok(false) => None,
+                        Err(e) => Some(Err(e)),
+                    },
+                    commit => Some(commit),
+                });
 
-                list_commits(&mut ui, repo.commits(&target)?, opts.short)
+                list_commits(&mut ui, walk, &opts, diffs)
             }
         },
-        None => list_commits(&mut ui, repo.commits(&repo.head()?)?, opts.short),
+        None => {
+            let diffs = (opts.patch || opts.stat).then_some(&repo);
+            list_commits(&mut ui, repo.commits(&repo.head()?)?, &opts, diffs)
+        }
     }
 }
 
-pub fn run(repo: Repo, opts: Opts) -> Result<(), Box<dyn Error>> {
-    if opts.no_pager {
-        render(TermRenderer::default(), repo, opts)
-    } else {
-        colored::control::set_override(true);
+pub fn run(repo: Repo, mut opts: Opts) -> Result<(), Box<dyn Error>> {
+    if term::plumbing() {
+        opts.date = DateFormat::IsoStrict;
+    }
 
+    // The one-line format has no room for a diffstat
+    if opts.short {
+        opts.stat = false;
+    }
+
+    // Validate the format before the pager is started
+    if let Some(format) = &opts.format {
+        parse_format(format)?;
+    }
+
+    if opts.no_pager || term::plumbing() {
+        render(
+            TermRenderer::default().with_width(term::width()),
+            repo,
+            opts,
+        )
+    } else {
         let cmd = opts.cmd.as_ref().map(Cmd::name).unwrap_or("commit");
-        let mut pager = Pager::new();
-        pager.set_prompt(format!("list {cmd}s, q to quit"))?;
+        let command = pager::configured(repo.config()?.get_string("core.pager").optional()?);
+        let pager = Pager::new(command.as_deref(), format!("list {cmd}s, q to quit"))?;
+        let dynamic = opts.patch || opts.stat;
+        let page = |pager: &mut Pager| {
+            render(
+                TermRenderer::new(pager).with_width(term::width()),
+                repo,
+                opts,
+            )
+        };
+
+        if dynamic {
+            pager.page_dynamic(page)
+        } else {
+            pager.page_all(page)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::fixture;
+
+    #[test]
+    fn test_max_count() {
+        let (_dir, git) = fixture::init();
+        let mut parents = vec![];
+
+        for message in ["first", "second", "third"] {
+            parents = vec![fixture::commit(&git, Some("HEAD"), &parents, message)];
+        }
+
+        let repo = Repo::from(git);
+
+        for args in [
+            &["-n", "2", "--short"][..],
+            &["-n", "2", "--short", "--graph"],
+        ] {
+            let opts = Opts::parse_from(std::iter::once("list").chain(args.iter().copied()));
+            let mut ui = TermRenderer::new(String::new());
+
+            list_commits(
+                &mut ui,
+                repo.commits(&repo.head().unwrap()).unwrap(),
+                &opts,
+                None,
+            )
+            .unwrap();
+
+            let output = ui.into_inner();
+
+            assert_eq!(output.lines().count(), 2);
+            assert!(output.contains("third") && output.contains("second"));
+            assert!(!output.contains("first"));
+        }
+    }
+
+    #[test]
+    fn test_grep_case() {
+        let (_dir, git) = fixture::init();
+        fixture::commit(&git, Some("HEAD"), &[], "Fix the parser");
+
+        let repo = Repo::from(git);
+        let commit = repo.head().unwrap().find_commit().unwrap();
+        let matches = |args: &[&str]| {
+            let opts = Opts::parse_from(std::iter::once("list").chain(args.iter().copied()));
+            Filter::from_opts(&opts).unwrap().matches(&commit)
+        };
+
+        assert!(matches(&["--grep", "fix"]));
+        assert!(matches(&["--grep", "Fix", "--case-sensitive"]));
+        assert!(!matches(&["--grep", "fix", "--case-sensitive"]));
+    }
+
+    #[test]
+    fn test_format_subject() {
+        let (_dir, git) = fixture::init();
+        fixture::commit(
+            &git,
+            Some("HEAD"),
+            &[],
+            "Fix the parser\nfor nested lists\n\nThe body",
+        );
+
+        let repo = Repo::from(git);
+        let commit = repo.head().unwrap().find_commit().unwrap();
+        let tokens = parse_format("%s|%b").unwrap();
+
+        assert_eq!(
+            format_commit(&tokens, &commit, DateFormat::Local).unwrap(),
+            "Fix the parser for nested lists|The body"
+        );
+    }
 
-        render(TermRenderer::new(&mut pager), repo, opts)?;
-        minus::page_all(pager)?;
+    #[test]
+    fn test_parse_format() {
+        let tokens = parse_format("%h %s%n(%an, 100%%)").unwrap();
 
-        Ok(())
+        assert!(matches!(
+            tokens.as_slice(),
+            [
+                Token::ShortHash,
+                Token::Text(space),
+                Token::Subject,
+                Token::Text(open),
+                Token::AuthorName,
+                Token::Text(close),
+            ] if space == " " && open == "\n(" && close == ", 100%)"
+        ));
+        assert!(parse_format("%x").is_err());
+        assert!(parse_format("%a").is_err());
+        assert!(parse_format("trailing %").is_err());
     }
 }
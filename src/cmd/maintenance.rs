@@ -0,0 +1,137 @@
+use std::error::Error;
+
+use clap::{Parser, ValueEnum};
+
+use crate::{
+    git::{RemoteOpts, Repo},
+    term::{
+        node::prelude::*,
+        render::{Render, TermRenderer},
+    },
+};
+
+#[derive(Parser)]
+#[clap(about = "Run tasks to optimize the repository")]
+pub struct Opts {
+    #[clap(subcommand)]
+    cmd: Cmd,
+}
+
+#[derive(Parser)]
+pub enum Cmd {
+    #[clap(about = "Run maintenance tasks")]
+    Run {
+        #[clap(
+            long = "task",
+            value_enum,
+            help = "Task to run, can be repeated (defaults to commit-graph and loose-objects)"
+        )]
+        tasks: Vec<Task>,
+    },
+}
+
+// Tasks run in declaration order, prefetch first so the commit-graph includes fetched commits
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum Task {
+    Prefetch,
+    CommitGraph,
+    LooseObjects,
+}
+
+impl Task {
+    fn name(self) -> &'static str {
+        match self {
+            Task::Prefetch => "prefetch",
+            Task::CommitGraph => "commit-graph",
+            Task::LooseObjects => "loose-objects",
+        }
+    }
+}
+
+/// Fetches all remotes into `refs/prefetch/` so regular refs, tags and the working tree are left
+/// alone.
+fn prefetch(repo: &Repo) -> Result<(), Box<dyn Error>> {
+    for remote in repo.remotes()? {
+        let remote = remote?;
+        let Some(name) = remote.name()?.map(ToString::to_string) else {
+            continue;
+        };
+
+        // An anonymous remote has no configured refspecs that libgit2 would update as well
+        repo.remote_anonymous(remote.url()?)?.prefetch(
+            RemoteOpts::default(),
+            &format!("+refs/heads/*:refs/prefetch/remotes/{name}/*"),
+        )?;
+    }
+
+    Ok(())
+}
+
+fn run_tasks(repo: &Repo, mut tasks: Vec<Task>) -> Result<(), Box<dyn Error>> {
+    let mut ui = TermRenderer::default();
+
+    if tasks.is_empty() {
+        tasks = vec![Task::CommitGraph, Task::LooseObjects];
+    }
+
+    tasks.sort();
+    tasks.dedup();
+
+    for task in tasks {
+        match task {
+            Task::Prefetch => prefetch(repo)?,
+            Task::CommitGraph => repo.write_commit_graph()?,
+            Task::LooseObjects => repo.pack_loose_objects()?,
+        }
+
+        ui.renderln(&message_with_icon(Icon::Check, task.name()))?;
+    }
+
+    Ok(())
+}
+
+pub fn run(repo: Repo, opts: Opts) -> Result<(), Box<dyn Error>> {
+    match opts.cmd {
+        Cmd::Run { tasks } => run_tasks(&repo, tasks),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::fixture;
+
+    #[test]
+    fn test_prefetch() {
+        let (_upstream_dir, upstream) = fixture::init();
+        let old = fixture::commit(&upstream, Some("HEAD"), &[], "old");
+        let branch = upstream.head().unwrap().shorthand().unwrap().to_string();
+
+        let (dir, git) = fixture::init();
+        git.remote("origin", upstream.workdir().unwrap().to_str().unwrap())
+            .unwrap()
+            .fetch(
+                &[] as &[&str],
+                Some(git2::FetchOptions::new().update_fetchhead(false)),
+                None,
+            )
+            .unwrap();
+
+        let new = fixture::commit(&upstream, Some("HEAD"), &[old], "new");
+        upstream
+            .tag_lightweight("v1", &upstream.find_object(new, None).unwrap(), false)
+            .unwrap();
+
+        let repo = Repo::from(git2::Repository::open(dir.path()).unwrap());
+        prefetch(&repo).unwrap();
+
+        let target = |name: &str| git.refname_to_id(name).ok();
+        assert_eq!(
+            target(&format!("refs/prefetch/remotes/origin/{branch}")),
+            Some(new)
+        );
+        assert_eq!(target(&format!("refs/remotes/origin/{branch}")), Some(old));
+        assert_eq!(target("refs/tags/v1"), None);
+        assert!(!git.path().join("FETCH_HEAD").exists());
+    }
+}
@@ -0,0 +1,139 @@
+use std::{
+    error::Error,
+    fs,
+    io::Write,
+    path::Path,
+    process::{Command, ExitStatus},
+};
+
+use clap::Parser;
+use git2::IndexEntry;
+use tempfile::NamedTempFile;
+
+use crate::{
+    cmd::{add::add_callback, resolve::render_conflicts},
+    git::{Config, Repo, Side},
+    term::{
+        node::prelude::*,
+        render::{Render, TermRenderer},
+    },
+};
+
+#[derive(Parser)]
+#[clap(about = "Run a merge tool to resolve conflicts")]
+pub struct Opts {
+    #[clap(short, long, help = "Merge tool to use (defaults to merge.tool)")]
+    tool: Option<String>,
+}
+
+fn write_blob(
+    repo: &Repo,
+    entry: Option<&IndexEntry>,
+    name: &str,
+    path: &Path,
+) -> Result<NamedTempFile, Box<dyn Error>> {
+    let suffix = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| format!(".{ext}"))
+        .unwrap_or_default();
+    let mut file = tempfile::Builder::new()
+        .prefix(&format!("{name}_"))
+        .suffix(&suffix)
+        .tempfile()?;
+
+    if let Some(entry) = entry {
+        file.write_all(repo.find_blob(entry.id)?.content())?;
+        file.flush()?;
+    }
+
+    Ok(file)
+}
+
+fn launch(
+    tool: &str,
+    cmd: Option<&str>,
+    base: &Path,
+    local: &Path,
+    remote: &Path,
+    merged: &Path,
+) -> Result<ExitStatus, std::io::Error> {
+    match cmd {
+        Some(cmd) => Command::new("sh")
+            .arg("-c")
+            .arg(cmd)
+            .env("BASE", base)
+            .env("LOCAL", local)
+            .env("REMOTE", remote)
+            .env("MERGED", merged)
+            .status(),
+        None => Command::new(tool)
+            .arg(base)
+            .arg(local)
+            .arg(remote)
+            .arg(merged)
+            .status(),
+    }
+}
+
+pub fn run(repo: Repo, opts: Opts) -> Result<(), Box<dyn Error>> {
+    let config = Config::open_default()?;
+    let tool = opts
+        .tool
+        .or(config.merge.tool)
+        .ok_or("No merge tool configured (set merge.tool)")?;
+    let cmd = config
+        .merge
+        .tools
+        .get(&tool)
+        .and_then(|tool| tool.cmd.as_deref());
+
+    let workdir = repo.workdir().ok_or("No working directory")?;
+    let mut index = repo.index()?;
+    let mut ui = TermRenderer::default();
+
+    for conflict in index.conflicts()? {
+        let path = Path::new(conflict.path()?);
+        let merged = workdir.join(path);
+        let base = write_blob(&repo, conflict.base(), "BASE", path)?;
+        let local = write_blob(&repo, conflict.side(Side::Ours), "LOCAL", path)?;
+        let remote = write_blob(&repo, conflict.side(Side::Theirs), "REMOTE", path)?;
+
+        ui.renderln(&block!(
+            text!("Merging: "),
+            text!(path.display().to_string())
+        ))?;
+
+        let before = fs::read(&merged).ok();
+        let status = launch(
+            &tool,
+            cmd,
+            base.path(),
+            local.path(),
+            remote.path(),
+            &merged,
+        )?;
+
+        if !status.success() {
+            ui.renderln(
+                &text!(format!("{tool} exited with {status}, skipping"))
+                    .with_status(Status::Warning),
+            )?;
+            continue;
+        }
+
+        if fs::read(&merged).ok() == before {
+            ui.renderln(
+                &text!("File unchanged, leaving it unresolved").with_status(Status::Warning),
+            )?;
+            continue;
+        }
+
+        index.add_path(path)?;
+        add_callback(path);
+    }
+
+    index.write()?;
+
+    render_conflicts(&mut ui, &index.conflicts()?)
+}
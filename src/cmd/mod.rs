@@ -4,12 +4,24 @@ pub mod branch;
 pub mod checkout;
 pub mod clone;
 pub mod commit;
+pub mod commit_graph;
+pub mod config;
+pub mod dash;
 pub mod diff;
 pub mod fetch;
 pub mod list;
+pub mod maintenance;
+pub mod mergetool;
+pub mod prune;
 pub mod pull;
 pub mod push;
+pub mod rebase;
+pub mod reflog;
+pub mod resolve;
 pub mod stash;
 pub mod status;
+pub mod submodule;
 pub mod sync;
+pub mod unstage;
 pub mod unstash;
+pub mod worktree;
@@ -0,0 +1,34 @@
+use std::error::Error;
+
+use clap::Parser;
+
+use crate::{
+    git::Repo,
+    term::{
+        node::prelude::*,
+        render::{Render, TermRenderer},
+    },
+};
+
+#[derive(Parser)]
+#[clap(about = "Remove unreachable objects")]
+pub struct Opts {
+    #[clap(
+        long,
+        default_value = "2.weeks.ago",
+        help = "Only prune objects older than this (e.g. '2.weeks.ago', 'now')"
+    )]
+    expire: String,
+}
+
+pub fn run(repo: Repo, opts: Opts) -> Result<(), Box<dyn Error>> {
+    let removed = repo.prune(&opts.expire)?;
+
+    let mut ui = TermRenderer::default();
+    ui.renderln(&message_with_icon(
+        Icon::Check,
+        format!("Removed {removed} unreachable objects"),
+    ))?;
+
+    Ok(())
+}
@@ -3,11 +3,11 @@ use std::error::Error;
 use clap::Parser;
 
 use crate::{
-    git::{RemoteOpts, Repo},
+    git::{Optional, RemoteOpts, Repo},
     term::{
         node::prelude::*,
         render::{Render, TermRenderer},
-        setup_progress_bar,
+        setup_progress, ProgressFormat,
     },
 };
 
@@ -17,45 +17,143 @@ pub struct Opts {
     #[clap(short, long, help = "Show detailed output")]
     details: bool,
 
-    #[clap(short, long, help = "Enable (experimental) rebase mode")]
+    #[clap(
+        short,
+        long,
+        overrides_with = "no_rebase",
+        help = "Rebase onto the upstream when it can't be fast-forwarded (defaults to pull.rebase)"
+    )]
     rebase: bool,
 
+    #[clap(
+        long,
+        overrides_with = "rebase",
+        help = "Don't rebase, even if pull.rebase is set"
+    )]
+    no_rebase: bool,
+
     #[clap(help = "Branch to pull from")]
     branch: Option<String>,
+
+    #[clap(
+        long,
+        value_enum,
+        default_value = "bar",
+        help = "Progress output format (json prints NDJSON events to stderr)"
+    )]
+    progress: ProgressFormat,
 }
 
-pub fn run(repo: Repo, opts: Opts) -> Result<(), Box<dyn Error>> {
+/// Reads `branch.<name>.rebase` or `pull.rebase`, any value other than false (e.g. `merges`)
+/// enables rebasing.
+fn rebase_config(config: &git2::Config, branch: &str) -> Result<Option<bool>, git2::Error> {
+    let value = match config
+        .get_string(&format!("branch.{branch}.rebase"))
+        .optional()?
     {
-        let mut head = repo.head()?;
-        let head_branch = head.shorthand()?.to_string();
-        let branch_name = opts.branch.as_deref().unwrap_or(&head_branch);
+        Some(value) => Some(value),
+        None => config.get_string("pull.rebase").optional()?,
+    };
+
+    Ok(value.map(|value| {
+        !matches!(
+            value.to_lowercase().as_str(),
+            "false" | "no" | "off" | "0" | ""
+        )
+    }))
+}
+
+/// Fetches and integrates the upstream changes, returns whether the status should be shown (not
+/// when up to date or stopped by conflicts).
+pub(crate) fn pull(repo: &Repo, opts: &Opts) -> Result<bool, Box<dyn Error>> {
+    let mut head = repo.head()?;
+    let head_branch = head.shorthand()?.to_string();
+    let branch_name = opts.branch.as_deref().unwrap_or(&head_branch);
 
-        let branch = repo.find_branch(branch_name)?;
-        let upstream = branch.upstream()?;
-        let remote = upstream.remote_name()?;
+    let branch = repo.find_branch(branch_name)?;
+    let upstream = branch.upstream()?;
+    let upstream_name = upstream.name()?.to_string();
+    let remote = upstream.remote_name()?;
 
-        let (tx, rx) = std::sync::mpsc::channel();
-        let handle = setup_progress_bar(rx);
+    let (tx, rx) = std::sync::mpsc::channel();
+    let handle = setup_progress(rx, opts.progress);
 
-        let mut remote = repo.find_remote(remote)?;
-        remote.fetch(RemoteOpts::default().with_progress(tx), branch_name)?;
+    let mut remote = repo.find_remote(remote)?;
+    remote.fetch(RemoteOpts::default().with_progress(tx), branch_name)?;
 
-        let _ = handle.join();
+    let _ = handle.join();
 
-        let oid = branch.upstream()?.target()?;
-        let upstream = repo.find_annotated_commit(oid)?;
-        let (analysis, _) = repo.merge_analysis(&upstream)?;
+    let rebase = match (opts.rebase, opts.no_rebase) {
+        (true, _) => true,
+        (_, true) => false,
+        _ => rebase_config(&repo.config()?, branch_name)?.unwrap_or(false),
+    };
 
-        if analysis.is_up_to_date() {
+    let oid = branch.upstream()?.target()?;
+    let upstream = repo.find_annotated_commit(oid)?;
+    let (analysis, _) = repo.merge_analysis(&upstream)?;
+
+    if analysis.is_up_to_date() {
+        let mut ui = TermRenderer::default();
+        ui.renderln(&message_with_icon(Icon::Check, "up to date"))?;
+        return Ok(false);
+    } else if analysis.is_fast_forward() {
+        let target = head.set_target(oid, "fast-forward")?;
+        repo.checkout_tree(&target.find_tree()?, true)?;
+    } else if rebase {
+        let todo = repo.rebase_todo(oid)?;
+        let conflicts = repo.rebase(oid, &todo, &mut |message| Ok(message.to_string()))?;
+
+        if !conflicts.is_empty() {
+            let mut ui = TermRenderer::default();
+            super::rebase::render_conflicts(&mut ui, &conflicts)?;
+            return Ok(false);
+        }
+    } else {
+        let message = format!("Merge {upstream_name} into {branch_name}");
+
+        if !repo.merge(&upstream, &message)? {
             let mut ui = TermRenderer::default();
-            return Ok(ui.renderln(&message_with_icon(Icon::Check, "up to date"))?);
-        } else if analysis.is_fast_forward() {
-            let target = head.set_target(oid, "fast-forward")?;
-            repo.checkout_tree(&target.find_tree()?, true)?;
-        } else {
-            return Err("unable to fast-forward (rebase not implemented)".into());
+            super::resolve::render_conflicts(&mut ui, &repo.index()?.conflicts()?)?;
+
+            ui.renderln(&block!(
+                spacer!(),
+                spacer!(),
+                continued!(text!(
+                    "Fix conflicts and run 'src commit' to conclude the merge"
+                ))
+            ))?;
+            return Ok(false);
         }
     }
 
-    super::status::run(gix::open(repo.path())?, super::status::Opts::default())
+    Ok(true)
+}
+
+pub fn run(repo: Repo, opts: Opts) -> Result<(), Box<dyn Error>> {
+    if pull(&repo, &opts)? {
+        super::status::run(gix::open(repo.path())?, super::status::Opts::default())?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rebase_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = git2::Config::open(&dir.path().join("config")).unwrap();
+
+        assert_eq!(rebase_config(&config, "main").unwrap(), None);
+
+        config.set_str("pull.rebase", "merges").unwrap();
+        assert_eq!(rebase_config(&config, "main").unwrap(), Some(true));
+
+        config.set_bool("branch.main.rebase", false).unwrap();
+        assert_eq!(rebase_config(&config, "main").unwrap(), Some(false));
+        assert_eq!(rebase_config(&config, "feature").unwrap(), Some(true));
+    }
 }
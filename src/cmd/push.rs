@@ -3,21 +3,44 @@ use std::error::Error;
 use clap::Parser;
 use colored::Colorize;
 use git2::ErrorCode;
+use gix::ObjectId;
 
 use crate::{
-    git::{Branch, Config, RemoteOpts, Repo},
+    git::{Branch, Config, Rejection, RemoteOpts, Repo, Update},
     term::{
         node::prelude::*,
         render::{Render, TermRenderer},
-        setup_progress_bar,
+        setup_progress, ProgressFormat,
     },
 };
 
 #[derive(Parser)]
 #[clap(about = "Update remote refs along with associated objects")]
 pub struct Opts {
-    #[clap(short, long, help = "Force push")]
+    #[clap(
+        short,
+        long,
+        help = "Force push, overwriting any changes on the remote"
+    )]
     force: bool,
+
+    #[clap(
+        long,
+        conflicts_with = "force",
+        help = "Force push, unless the remote branch changed since it was last fetched"
+    )]
+    force_with_lease: bool,
+
+    #[clap(
+        long,
+        value_enum,
+        default_value = "bar",
+        help = "Progress output format (json prints NDJSON events to stderr)"
+    )]
+    progress: ProgressFormat,
+
+    #[clap(long, help = "Show what would be pushed without updating the remote")]
+    dry_run: bool,
 }
 
 fn set_tracking_branch(
@@ -33,6 +56,78 @@ fn set_tracking_branch(
     Ok(())
 }
 
+fn short(oid: git2::Oid) -> String {
+    oid.to_string()[..7].to_string()
+}
+
+fn rejected_error(rejected: &[Rejection]) -> Box<dyn Error> {
+    let mut message = String::new();
+
+    for rejection in rejected {
+        let update = &rejection.update;
+        message.push_str(&format!(
+            "push rejected for {} ({} → {}): {}\n",
+            update.refname,
+            short(update.src),
+            short(update.dst),
+            rejection.reason
+        ));
+    }
+
+    if rejected.iter().any(Rejection::is_non_fast_forward) {
+        message.push_str(
+            "the remote contains commits that you don't have locally, run 'src pull --rebase' to \
+             integrate them or push with --force-with-lease to overwrite them",
+        );
+    }
+
+    message.trim_end().into()
+}
+
+fn render_updates(ui: &mut impl Render, updates: &[Update]) -> Result<(), Box<dyn Error>> {
+    let mut lines = vec![];
+
+    for update in updates {
+        let old = if update.src.is_zero() {
+            text!("(new)")
+        } else {
+            Node::Attribute(Attribute::CommitShort(ObjectId::try_from(
+                update.src.as_bytes(),
+            )?))
+        };
+
+        lines.push(block!(
+            spacer!(),
+            spacer!(),
+            text!(update.refname.clone()),
+            spacer!(),
+            old,
+            text!(" → "),
+            Node::Attribute(Attribute::CommitShort(ObjectId::try_from(
+                update.dst.as_bytes()
+            )?))
+        ));
+    }
+
+    Ok(ui.renderln(&Node::Group(
+        "Would update (dry run)".into(),
+        Some(lines.len()),
+        Box::new(Node::MultiLine(lines)),
+    ))?)
+}
+
+/// Prints the messages the remote sent along (e.g. a link to open a pull request).
+pub(crate) fn render_reply(stdout: &[u8]) {
+    if let Ok(msg) = std::str::from_utf8(stdout)
+        .map(|s| s.trim_matches(|c: char| c.is_whitespace() || c == '\0'))
+    {
+        if !msg.is_empty() {
+            println!("\nReply:");
+            println!("{}", msg.bright_black());
+        }
+    }
+}
+
 pub fn run(repo: Repo, opts: Opts) -> Result<(), Box<dyn Error>> {
     let head = repo.head()?;
     let refname = head.name()?.to_string();
@@ -66,10 +161,20 @@ pub fn run(repo: Repo, opts: Opts) -> Result<(), Box<dyn Error>> {
     ))?;
 
     let (tx, rx) = std::sync::mpsc::channel();
-    let handle = setup_progress_bar(rx);
+    let handle = setup_progress(rx, opts.progress);
+    let mut remote_opts = RemoteOpts::default().with_progress(tx);
+
+    if opts.force_with_lease {
+        remote_opts = remote_opts.with_compare(target);
+    }
+
+    if opts.dry_run {
+        remote_opts = remote_opts.with_dry_run();
+    }
+
     let reply = remote.push(
-        RemoteOpts::default().with_progress(tx).with_compare(target),
-        &if opts.force {
+        remote_opts,
+        &if opts.force || opts.force_with_lease {
             format!("+{refname}")
         } else {
             refname
@@ -78,16 +183,16 @@ pub fn run(repo: Repo, opts: Opts) -> Result<(), Box<dyn Error>> {
 
     let _ = handle.join();
 
-    ui.renderln(&message_with_icon(Icon::Check, "done"))?;
+    if !reply.rejected.is_empty() {
+        return Err(rejected_error(&reply.rejected));
+    }
 
-    if let Ok(msg) = std::str::from_utf8(&reply.stdout)
-        .map(|s| s.trim_matches(|c: char| c.is_whitespace() || c == '\0'))
-    {
-        if !msg.is_empty() {
-            println!("\nReply:");
-            println!("{}", msg.bright_black());
-        }
+    if opts.dry_run {
+        return render_updates(&mut ui, &reply.updates);
     }
 
+    ui.renderln(&message_with_icon(Icon::Check, "done"))?;
+    render_reply(&reply.stdout);
+
     Ok(())
 }
@@ -0,0 +1,131 @@
+use std::{error::Error, str::FromStr};
+
+use clap::Parser;
+
+use crate::{
+    cmd::branch::resolve_commit,
+    git::{Config, Optional, Repo},
+    rebase::RebaseOp,
+    term::{
+        self,
+        node::prelude::*,
+        render::{Render, TermRenderer},
+    },
+};
+
+#[derive(Parser)]
+#[clap(about = "Reapply commits on top of another base")]
+pub struct Opts {
+    #[clap(short, long, help = "Edit the list of commits before rebasing")]
+    interactive: bool,
+
+    #[clap(long, conflicts_with_all = ["continue_rebase", "interactive"], help = "Abort the rebase in progress")]
+    abort: bool,
+
+    #[clap(
+        long = "continue",
+        conflicts_with = "interactive",
+        help = "Continue the rebase in progress after resolving the conflicts"
+    )]
+    continue_rebase: bool,
+
+    #[clap(
+        required_unless_present_any = ["abort", "continue_rebase"],
+        conflicts_with_all = ["abort", "continue_rebase"],
+        help = "Branch or commit to rebase onto"
+    )]
+    upstream: Option<String>,
+}
+
+const TODO_HELP: &str = "
+# Commands:
+# p, pick <commit> = use commit
+# r, reword <commit> = use commit, but edit the commit message
+# s, squash <commit> = use commit, but meld into previous commit
+# f, fixup <commit> = like \"squash\", but discard this commit's message
+# d, drop <commit> = remove commit
+#
+# Commits are applied from top to bottom, move the lines to reorder them.
+# Removing a line drops the commit as well.
+";
+
+fn resolve_upstream(repo: &Repo, spec: &str) -> Result<git2::Oid, Box<dyn Error>> {
+    match repo.find_remote_branch(spec).optional()? {
+        Some(branch) => Ok(branch.target()?),
+        None => Ok(resolve_commit(repo, spec)?.id()),
+    }
+}
+
+fn edit_todo(editor: Option<&str>, todo: &[RebaseOp]) -> Result<Vec<RebaseOp>, Box<dyn Error>> {
+    let content = todo.iter().map(|op| format!("{op}\n")).collect::<String>();
+
+    Ok(term::edit(editor, &format!("{content}{TODO_HELP}"))?
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(RebaseOp::from_str)
+        .collect::<Result<Vec<_>, _>>()?)
+}
+
+pub fn render_conflicts(ui: &mut impl Render, conflicts: &[String]) -> Result<(), Box<dyn Error>> {
+    if conflicts.is_empty() {
+        return Ok(ui.renderln(&message_with_icon(Icon::Check, "done"))?);
+    }
+
+    let mut lines = conflicts
+        .iter()
+        .map(|path| {
+            block!(
+                spacer!(),
+                spacer!(),
+                Node::Indicator(Indicator::Conflict),
+                spacer!(),
+                text!(path.clone())
+            )
+        })
+        .collect::<Vec<_>>();
+
+    lines.push(block!(
+        spacer!(),
+        spacer!(),
+        continued!(text!(
+            "Fix conflicts, stage them and run 'src rebase --continue'"
+        ))
+    ));
+
+    Ok(ui.renderln(&Node::Group(
+        "Rebase stopped".into(),
+        Some(conflicts.len()),
+        Box::new(Node::MultiLine(lines)),
+    ))?)
+}
+
+pub fn run(repo: Repo, opts: Opts) -> Result<(), Box<dyn Error>> {
+    let config = Config::open_default()?;
+    let editor = config.core.editor.as_deref();
+    let mut ui = TermRenderer::default();
+
+    if opts.abort {
+        repo.abort_rebase()?;
+        return Ok(ui.renderln(&message_with_icon(Icon::Check, "rebase aborted"))?);
+    }
+
+    if opts.continue_rebase {
+        let conflicts = repo.continue_rebase(&mut |message| term::edit(editor, message))?;
+        return render_conflicts(&mut ui, &conflicts);
+    }
+
+    let upstream = resolve_upstream(&repo, opts.upstream.as_deref().unwrap_or_default())?;
+    let mut todo = repo.rebase_todo(upstream)?;
+
+    if opts.interactive {
+        todo = edit_todo(editor, &todo)?;
+
+        if todo.is_empty() {
+            return Ok(ui.renderln(&message_with_icon(Icon::Check, "nothing to do"))?);
+        }
+    }
+
+    let conflicts = repo.rebase(upstream, &todo, &mut |message| term::edit(editor, message))?;
+
+    render_conflicts(&mut ui, &conflicts)
+}
@@ -0,0 +1,47 @@
+use std::error::Error;
+
+use clap::Parser;
+
+use crate::{
+    git::Repo,
+    term::{
+        node::prelude::*,
+        render::{Render, TermRenderer},
+    },
+};
+
+#[derive(Parser)]
+#[clap(about = "Manage reflog information")]
+pub struct Opts {
+    #[clap(subcommand)]
+    cmd: Cmd,
+}
+
+#[derive(Parser)]
+pub enum Cmd {
+    #[clap(about = "Remove old reflog entries")]
+    Expire {
+        #[clap(
+            long,
+            default_value = "90.days.ago",
+            help = "Remove entries older than this (e.g. '2.weeks.ago', 'now')"
+        )]
+        expire: String,
+    },
+}
+
+pub fn run(repo: Repo, opts: Opts) -> Result<(), Box<dyn Error>> {
+    match opts.cmd {
+        Cmd::Expire { expire } => {
+            let removed = repo.expire_reflogs(&expire)?;
+
+            let mut ui = TermRenderer::default();
+            ui.renderln(&message_with_icon(
+                Icon::Check,
+                format!("Removed {removed} reflog entries"),
+            ))?;
+
+            Ok(())
+        }
+    }
+}
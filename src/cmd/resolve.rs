@@ -0,0 +1,220 @@
+use std::{
+    env,
+    error::Error,
+    fs, io,
+    path::{Component, Path, PathBuf},
+};
+
+use clap::{Parser, ValueHint};
+
+use crate::{
+    cmd::add::add_callback,
+    git::{Conflict, Repo, Side},
+    term::{
+        node::prelude::*,
+        render::{Render, TermRenderer},
+    },
+};
+
+#[derive(Parser)]
+#[clap(about = "Mark conflicts as resolved")]
+pub struct Opts {
+    #[clap(short, long, help = "Resolve all conflicted files")]
+    all: bool,
+
+    #[clap(long, conflicts_with = "theirs", help = "Resolve using our version")]
+    ours: bool,
+
+    #[clap(long, help = "Resolve using their version")]
+    theirs: bool,
+
+    #[clap(value_hint = ValueHint::AnyPath, required_unless_present = "all")]
+    paths: Vec<String>,
+}
+
+/// Makes a path given relative to `cwd` relative to the root of the working tree, the file itself
+/// may be gone.
+fn repo_path(workdir: &Path, cwd: &Path, path: &str) -> Result<String, Box<dyn Error>> {
+    let mut absolute = PathBuf::new();
+
+    for component in cwd.join(path).components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                absolute.pop();
+            }
+            component => absolute.push(component),
+        }
+    }
+
+    Ok(absolute
+        .strip_prefix(workdir)
+        .map_err(|_| format!("Path is outside the repository: {path}"))?
+        .to_str()
+        .ok_or("invalid path")?
+        .to_string())
+}
+
+/// Applies the executable bit of the index entry `mode` to the file.
+#[cfg(unix)]
+fn set_mode(path: &Path, mode: u32) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mut permissions = fs::metadata(path)?.permissions();
+    let current = permissions.mode();
+
+    // Like git, only the ones who can read the file get to execute it
+    permissions.set_mode(if mode & 0o111 != 0 {
+        current | ((current & 0o444) >> 2)
+    } else {
+        current & !0o111
+    });
+
+    fs::set_permissions(path, permissions)
+}
+
+#[cfg(not(unix))]
+fn set_mode(_path: &Path, _mode: u32) -> io::Result<()> {
+    Ok(())
+}
+
+fn checkout_side(
+    repo: &Repo,
+    path: &Path,
+    conflict: &Conflict,
+    side: Side,
+) -> Result<(), Box<dyn Error>> {
+    match conflict.side(side) {
+        Some(entry) => {
+            fs::write(path, repo.find_blob(entry.id)?.content())?;
+            set_mode(path, entry.mode)?;
+        }
+        // The file was deleted on this side
+        None if path.exists() => fs::remove_file(path)?,
+        None => {}
+    }
+
+    Ok(())
+}
+
+pub fn render_conflicts(
+    ui: &mut impl Render,
+    conflicts: &[Conflict],
+) -> Result<(), Box<dyn Error>> {
+    if conflicts.is_empty() {
+        ui.renderln(&message_with_icon(Icon::Check, "All conflicts resolved"))?;
+        return Ok(());
+    }
+
+    let mut lines = vec![];
+
+    for conflict in conflicts.iter() {
+        lines.push(block!(
+            spacer!(),
+            spacer!(),
+            Node::Indicator(Indicator::Conflict),
+            spacer!(),
+            text!(conflict.path()?.to_string())
+        ));
+    }
+
+    ui.renderln(&Node::Group(
+        "Unresolved Conflicts".into(),
+        Some(conflicts.len()),
+        Box::new(Node::MultiLine(lines)),
+    ))?;
+
+    Ok(())
+}
+
+pub fn run(repo: Repo, opts: Opts) -> Result<(), Box<dyn Error>> {
+    let workdir = repo.workdir().ok_or("No working directory")?;
+    let mut index = repo.index()?;
+    let conflicts = index.conflicts()?;
+    let side = match (opts.ours, opts.theirs) {
+        (true, _) => Some(Side::Ours),
+        (_, true) => Some(Side::Theirs),
+        _ => None,
+    };
+    let paths = if opts.all {
+        conflicts
+            .iter()
+            .map(|c| c.path().map(ToString::to_string))
+            .collect::<Result<Vec<_>, _>>()?
+    } else {
+        let root = workdir.canonicalize()?;
+        let cwd = env::current_dir()?.canonicalize()?;
+
+        opts.paths
+            .iter()
+            .map(|path| repo_path(&root, &cwd, path))
+            .collect::<Result<Vec<_>, _>>()?
+    };
+
+    for path in paths {
+        if let Some(side) = side {
+            let conflict = conflicts
+                .iter()
+                .find(|c| c.path().ok() == Some(path.as_str()))
+                .ok_or_else(|| format!("No conflict found for: {path}"))?;
+
+            checkout_side(&repo, &workdir.join(&path), conflict, side)?;
+        }
+
+        let path = Path::new(&path);
+
+        // Resolving a conflict by deleting the file should remove it from the index
+        if workdir.join(path).exists() {
+            index.add_path(path)?;
+        } else {
+            index.remove_path(path)?;
+        }
+
+        add_callback(path);
+    }
+
+    index.write()?;
+
+    render_conflicts(&mut TermRenderer::default(), &index.conflicts()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repo_path() {
+        let workdir = Path::new("/repo");
+        let cwd = Path::new("/repo/src/cmd");
+
+        assert_eq!(repo_path(workdir, cwd, "add.rs").unwrap(), "src/cmd/add.rs");
+        assert_eq!(
+            repo_path(workdir, cwd, "./../main.rs").unwrap(),
+            "src/main.rs"
+        );
+        assert_eq!(
+            repo_path(workdir, cwd, "../../Cargo.toml").unwrap(),
+            "Cargo.toml"
+        );
+        assert!(repo_path(workdir, cwd, "../../../other").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_set_mode() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("run.sh");
+        let mode = || fs::metadata(&path).unwrap().permissions().mode() & 0o777;
+
+        fs::write(&path, "#!/bin/sh\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+
+        set_mode(&path, 0o100755).unwrap();
+        assert_eq!(mode(), 0o750);
+
+        set_mode(&path, 0o100644).unwrap();
+        assert_eq!(mode(), 0o640);
+    }
+}
@@ -3,8 +3,10 @@ use std::error::Error;
 use clap::Parser;
 
 use crate::{
-    git::Repo,
+    cmd::branch::resolve_commit,
+    git::{DateFormat, Repo},
     term::{
+        self,
         node::prelude::*,
         render::{Render, TermRenderer},
     },
@@ -12,9 +14,110 @@ use crate::{
 
 #[derive(Parser)]
 #[clap(about = "Stash the changes in a dirty working directory away")]
-pub struct Opts {}
+pub struct Opts {
+    #[clap(subcommand)]
+    cmd: Option<Cmd>,
+}
+
+#[derive(Parser)]
+pub enum Cmd {
+    #[clap(about = "Drop a stash entry")]
+    Drop {
+        #[clap(help = "Stash index", default_value = "0")]
+        index: usize,
+
+        #[clap(short, long, help = "Drop without prompting")]
+        yes: bool,
+    },
+
+    #[clap(about = "Create a stash commit without storing it, prints its id")]
+    Create {
+        #[clap(help = "Stash message")]
+        message: Option<String>,
+    },
+
+    #[clap(about = "Store a stash commit created with `stash create` in the stash reflog")]
+    Store {
+        #[clap(help = "Stash commit")]
+        commit: String,
+
+        #[clap(short, long, help = "Reflog message")]
+        message: Option<String>,
+    },
+
+    #[clap(about = "Show the changes recorded in a stash entry")]
+    Show {
+        #[clap(help = "Stash index", default_value = "0")]
+        index: usize,
+
+        #[clap(short, long, default_value = "false")]
+        patch: bool,
+
+        #[clap(long, help = "Disable the pager")]
+        no_pager: bool,
+    },
+}
+
+fn drop_stash(mut repo: Repo, index: usize, yes: bool) -> Result<(), Box<dyn Error>> {
+    let mut ui = TermRenderer::default();
+
+    if !yes {
+        {
+            let commit = repo
+                .stashes()?
+                .nth(index)
+                .transpose()?
+                .ok_or_else(|| format!("stash not found: {index}"))?;
+
+            ui.renderln(&multi_line!(
+                dimmed!(commit.headers_ui(DateFormat::Local)),
+                spacer!(),
+                text!(commit.message_formatted())
+            ))?;
+        }
+
+        if !term::confirm("Drop this stash?")? {
+            return Ok(());
+        }
+    }
 
-pub fn run(mut repo: Repo, _opts: Opts) -> Result<(), Box<dyn Error>> {
+    repo.drop_stash(index)?;
+    ui.renderln(&message_with_icon(Icon::Check, "Stash dropped"))?;
+
+    Ok(())
+}
+
+fn show_stash(
+    mut repo: Repo,
+    index: usize,
+    patch: bool,
+    no_pager: bool,
+) -> Result<(), Box<dyn Error>> {
+    let diff = {
+        let diff = repo.stash_diff(index)?;
+        super::diff::render_diff(&diff, None)?
+    };
+
+    super::diff::display(&repo, diff, format!("stash@{{{index}}}"), patch, no_pager)
+}
+
+fn create_stash(repo: Repo, message: Option<&str>) -> Result<(), Box<dyn Error>> {
+    if let Some(oid) = repo.create_stash(message)? {
+        println!("{oid}");
+    }
+
+    Ok(())
+}
+
+fn store_stash(repo: Repo, commit: &str, message: Option<&str>) -> Result<(), Box<dyn Error>> {
+    let oid = resolve_commit(&repo, commit)?.id();
+
+    repo.store_stash(oid, message.unwrap_or("Created via \"src stash store\"."))?;
+
+    Ok(())
+}
+
+fn save_stash(mut repo: Repo) -> Result<(), Box<dyn Error>> {
     let message = {
         let head = repo.head()?;
         let commit = head.find_commit()?;
@@ -30,3 +133,17 @@ pub fn run(mut repo: Repo, _opts: Opts) -> Result<(), Box<dyn Error>> {
 
     Ok(())
 }
+
+pub fn run(repo: Repo, opts: Opts) -> Result<(), Box<dyn Error>> {
+    match opts.cmd {
+        Some(Cmd::Drop { index, yes }) => drop_stash(repo, index, yes),
+        Some(Cmd::Create { message }) => create_stash(repo, message.as_deref()),
+        Some(Cmd::Store { commit, message }) => store_stash(repo, &commit, message.as_deref()),
+        Some(Cmd::Show {
+            index,
+            patch,
+            no_pager,
+        }) => show_stash(repo, index, patch, no_pager),
+        None => save_stash(repo),
+    }
+}
@@ -1,4 +1,4 @@
-use std::error::Error;
+use std::{collections::BTreeMap, error::Error, io};
 
 use clap::Parser;
 use gix::{
@@ -7,17 +7,22 @@ use gix::{
     refs::Category,
     remote,
     state::InProgress,
-    status::{index_worktree, plumbing::index_as_worktree::EntryStatus, Item, UntrackedFiles},
+    status::{
+        index_worktree,
+        plumbing::index_as_worktree::{Change, EntryStatus},
+        Item, UntrackedFiles,
+    },
     Repository,
 };
-use minus::Pager;
 use tracing::instrument;
 
 use crate::{
     graph::Graph,
-    rebase::{Rebase, RebaseOperationType},
+    rebase::{ApplyProgress, Rebase, RebaseError, REBASE_DIR},
     term::{
+        self,
         node::prelude::*,
+        pager::{self, Pager},
         render::{Render, TermRenderer},
     },
 };
@@ -27,13 +32,41 @@ use crate::{
 pub struct Opts {
     #[clap(long, help = "Disable the pager")]
     no_pager: bool,
+
+    #[clap(
+        long,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        help = "Compute commits ahead/behind the upstream (defaults to status.aheadBehind)"
+    )]
+    ahead_behind: Option<bool>,
+
+    #[clap(
+        long,
+        conflicts_with = "json",
+        help = "Machine-readable output (`XY path` per file)"
+    )]
+    porcelain: bool,
+
+    #[clap(long, help = "Machine-readable output as JSON")]
+    json: bool,
+
+    #[clap(
+        short,
+        long,
+        value_enum,
+        help = "Show untracked files, `all` expands untracked directories (defaults to status.showUntrackedFiles)"
+    )]
+    untracked_files: Option<Untracked>,
 }
 
-#[instrument(skip(ui, repo, graph), ret(Debug))]
-fn render_branch(
+#[instrument(skip(ui, repo), ret(Debug))]
+pub(crate) fn render_branch(
     ui: &mut impl Render,
     repo: &Repository,
-    graph: Option<&Graph>,
+    upstream: Option<&str>,
+    counts: Option<(usize, usize)>,
 ) -> Result<(), Box<dyn Error>> {
     let head = repo.head()?;
 
@@ -59,15 +92,22 @@ fn render_branch(
             group.push(spacer!());
 
             if let Some(indicators) =
-                graph.and_then(|graph| remote_state_indicators(graph).ok().flatten())
+                counts.and_then(|(ahead, behind)| ahead_behind_indicators(ahead, behind))
             {
                 group.push(label!(indicators));
                 group.push(spacer!());
             };
 
+            if let Some(upstream) = upstream {
+                group.push(Node::Attribute(Attribute::Remote(
+                    upstream.to_string().into(),
+                )));
+                group.push(spacer!());
+            }
+
             let commit = object.into_commit();
 
-            group.push(Node::text_capped(
+            group.push(Node::Capped(
                 commit
                     .message()?
                     .title
@@ -75,8 +115,8 @@ fn render_branch(
                     .lines()
                     .next()
                     .unwrap_or_default()
-                    .to_string(),
-                75,
+                    .to_string()
+                    .into(),
             ));
 
             ui.renderln(&Node::Block(group))?;
@@ -93,11 +133,9 @@ fn render_branch(
     }
 }
 
-#[instrument(skip(graph), ret(Debug))]
-fn remote_state_indicators(graph: &Graph) -> Result<Option<Node>, Box<dyn Error>> {
-    let (ahead, behind) = (graph.ahead.len(), graph.behind.len());
-
-    Ok(if ahead == 0 && behind == 0 {
+/// Arrows with the number of commits ahead and behind, `None` when in sync.
+pub(crate) fn ahead_behind_indicators(ahead: usize, behind: usize) -> Option<Node> {
+    if ahead == 0 && behind == 0 {
         None
     } else if ahead == 0 && behind != 0 {
         Some(block!(
@@ -120,24 +158,15 @@ fn remote_state_indicators(graph: &Graph) -> Result<Option<Node>, Box<dyn Error>
             icon!(ArrowDown).with_status(Status::Error),
             text!(behind.to_string())
         ))
-    })
+    }
 }
 
-#[instrument(skip(ui, repo), ret(Debug))]
-fn render_rebase(ui: &mut impl Render, repo: &Repository) -> Result<(), Box<dyn Error>> {
-    let rebase = Rebase::from_repo(repo)?;
+fn rebase_operations(rebase: &Rebase) -> Vec<Node> {
     let mut children = vec![];
 
     for op in rebase.operations.iter() {
         let id = op.oid.to_string();
-        let kind = match op.ty {
-            RebaseOperationType::Pick => "pick",
-            RebaseOperationType::Reword => "reword",
-            RebaseOperationType::Edit => "edit",
-            RebaseOperationType::Squash => "squash",
-            RebaseOperationType::Fixup => "fixup",
-            RebaseOperationType::Exec => "exec",
-        };
+        let kind = op.ty.name();
 
         children.push(block!(
             spacer!(),
@@ -150,10 +179,23 @@ fn render_rebase(ui: &mut impl Render, repo: &Repository) -> Result<(), Box<dyn
         ));
     }
 
+    children
+}
+
+#[instrument(skip(ui, rebase), ret(Debug))]
+fn render_rebase(
+    ui: &mut impl Render,
+    rebase: Rebase,
+    command: &str,
+) -> Result<(), Box<dyn Error>> {
+    let mut children = rebase_operations(&rebase);
+
     children.push(block!(
         spacer!(),
         spacer!(),
-        continued!(text!("Fix conflicts and run 'git rebase --continue'"))
+        continued!(text!(format!(
+            "Fix conflicts and run '{command} --continue'"
+        )))
     ));
 
     ui.renderln(&Node::Group(
@@ -166,10 +208,59 @@ fn render_rebase(ui: &mut impl Render, repo: &Repository) -> Result<(), Box<dyn
 }
 
 #[instrument(skip(ui, repo), ret(Debug))]
-fn render_state(ui: &mut impl Render, repo: &Repository) -> Result<(), Box<dyn Error>> {
+fn render_apply(
+    ui: &mut impl Render,
+    repo: &Repository,
+    rebase: bool,
+) -> Result<(), Box<dyn Error>> {
+    let progress = ApplyProgress::from_repo(repo)?;
+    let mut children = if rebase {
+        match Rebase::from_repo(repo) {
+            Ok(rebase) => rebase_operations(&rebase),
+            Err(RebaseError::Io(e)) if e.kind() == io::ErrorKind::NotFound => vec![],
+            Err(e) => return Err(e.into()),
+        }
+    } else {
+        vec![]
+    };
+
+    children.push(block!(
+        spacer!(),
+        spacer!(),
+        text!(format!(
+            "Applying patch {}/{}",
+            progress.next, progress.last
+        ))
+    ));
+    children.push(block!(
+        spacer!(),
+        spacer!(),
+        continued!(text!(if rebase {
+            "Fix conflicts and run 'git rebase --continue'"
+        } else {
+            "Fix conflicts and run 'git am --continue'"
+        }))
+    ));
+
+    ui.renderln(&Node::Group(
+        if rebase { "Rebase" } else { "Apply mailbox" }.into(),
+        Some(progress.last),
+        Box::new(Node::MultiLine(children)),
+    ))?;
+
+    Ok(())
+}
+
+#[instrument(skip(ui, repo), ret(Debug))]
+pub(crate) fn render_state(ui: &mut impl Render, repo: &Repository) -> Result<(), Box<dyn Error>> {
+    if repo.path().join(REBASE_DIR).exists() {
+        return render_rebase(ui, Rebase::from_src(repo)?, "src rebase");
+    }
+
     match repo.state() {
         Some(state) => match state {
-            InProgress::ApplyMailbox | InProgress::ApplyMailboxRebase => todo!(),
+            InProgress::ApplyMailbox => render_apply(ui, repo, false),
+            InProgress::ApplyMailboxRebase => render_apply(ui, repo, true),
             InProgress::Bisect => {
                 ui.renderln(&text!("Bisect in progress"))?;
                 Ok(())
@@ -182,7 +273,9 @@ fn render_state(ui: &mut impl Render, repo: &Repository) -> Result<(), Box<dyn E
                 ui.renderln(&text!("Merge in progress"))?;
                 Ok(())
             }
-            InProgress::Rebase | InProgress::RebaseInteractive => render_rebase(ui, repo),
+            InProgress::Rebase | InProgress::RebaseInteractive => {
+                render_rebase(ui, Rebase::from_repo(repo)?, "git rebase")
+            }
             InProgress::Revert | InProgress::RevertSequence => {
                 ui.renderln(&text!("Revert in progress"))?;
                 Ok(())
@@ -210,8 +303,8 @@ fn render_commits(ui: &mut impl Render, graph: Graph) -> Result<(), Box<dyn Erro
         let count = commits.len();
         let mut lines = vec![];
 
-        for info in commits {
-            let commit = info.object()?;
+        for id in commits {
+            let commit = id.object()?.try_into_commit()?;
             let id = commit.id().to_string();
 
             lines.push(block!(
@@ -241,72 +334,132 @@ fn render_commits(ui: &mut impl Render, graph: Graph) -> Result<(), Box<dyn Erro
     Ok(ui.renderln(&Node::MultiLine(children))?)
 }
 
-#[instrument(skip(ui, repo), ret(Debug))]
-fn render_changes(ui: &mut impl Render, repo: &Repository) -> Result<(), Box<dyn Error>> {
-    let mut children = vec![];
-    let status = repo
-        .status(progress::Discard)?
-        .untracked_files(UntrackedFiles::Files);
-    let entries = status.into_iter([])?.collect::<Result<Vec<_>, _>>()?;
-    let (staged, unstaged): (Vec<_>, Vec<_>) = entries
-        .into_iter()
-        .partition(|e| matches!(e, Item::TreeIndex(_)));
-    let groups = [("Staged Changes", staged), ("Unstaged Changes", unstaged)];
+#[derive(Clone, Copy, clap::ValueEnum)]
+pub enum Untracked {
+    No,
+    Normal,
+    All,
+}
 
-    for (name, items) in groups {
-        if items.is_empty() {
-            continue;
+impl From<Untracked> for UntrackedFiles {
+    fn from(untracked: Untracked) -> Self {
+        match untracked {
+            Untracked::No => UntrackedFiles::None,
+            Untracked::Normal => UntrackedFiles::Collapsed,
+            Untracked::All => UntrackedFiles::Files,
         }
+    }
+}
 
-        let count = items.len();
-        let mut lines = vec![];
+/// Maps `status.showUntrackedFiles` to the untracked files mode, `normal` is the default.
+fn untracked_files(repo: &Repository) -> UntrackedFiles {
+    match repo
+        .config_snapshot()
+        .string("status.showUntrackedFiles")
+        .as_deref()
+        .and_then(|value| value.to_str().ok())
+    {
+        Some("no" | "false" | "off" | "0") => UntrackedFiles::None,
+        Some("all") => UntrackedFiles::Files,
+        _ => UntrackedFiles::Collapsed,
+    }
+}
 
-        for item in items {
-            let indicator = match &item {
-                Item::IndexWorktree(item) => match item {
-                    index_worktree::Item::Modification { status, .. } => match status {
-                        EntryStatus::Conflict(_) => Indicator::Conflict,
-                        _ => Indicator::Modified,
-                    },
-                    index_worktree::Item::DirectoryContents { entry, .. } => match entry.status {
-                        gix::dir::entry::Status::Untracked => Indicator::New,
-                        _ => Indicator::Modified,
-                    },
-                    index_worktree::Item::Rewrite { .. } => Indicator::Renamed,
-                },
-                Item::TreeIndex(change) => match change {
-                    gix::diff::index::ChangeRef::Addition { .. } => Indicator::New,
-                    gix::diff::index::ChangeRef::Deletion { .. } => Indicator::Deleted,
-                    gix::diff::index::ChangeRef::Modification { .. } => Indicator::Modified,
-                    gix::diff::index::ChangeRef::Rewrite { .. } => Indicator::Renamed,
-                },
-            };
+/// Path of the item, collapsed untracked directories get a trailing `/`.
+fn location(item: &Item) -> String {
+    match item {
+        Item::IndexWorktree(index_worktree::Item::DirectoryContents { entry, .. })
+            if entry.disk_kind == Some(gix::dir::entry::Kind::Directory) =>
+        {
+            format!("{}/", entry.rela_path)
+        }
+        _ => item.location().to_string(),
+    }
+}
 
-            lines.push(block!(
-                spacer!(),
-                spacer!(),
-                Node::Indicator(indicator),
-                spacer!(),
-                text!(item.location().to_string())
-            ));
+fn indicator(item: &Item) -> Indicator {
+    match item {
+        Item::IndexWorktree(item) => match item {
+            index_worktree::Item::Modification { status, .. } => match status {
+                EntryStatus::Conflict(_) => Indicator::Conflict,
+                _ => Indicator::Modified,
+            },
+            index_worktree::Item::DirectoryContents { entry, .. } => match entry.status {
+                gix::dir::entry::Status::Untracked => Indicator::New,
+                _ => Indicator::Modified,
+            },
+            index_worktree::Item::Rewrite { .. } => Indicator::Renamed,
+        },
+        Item::TreeIndex(change) => match change {
+            gix::diff::index::ChangeRef::Addition { .. } => Indicator::New,
+            gix::diff::index::ChangeRef::Deletion { .. } => Indicator::Deleted,
+            gix::diff::index::ChangeRef::Modification { .. } => Indicator::Modified,
+            gix::diff::index::ChangeRef::Rewrite { .. } => Indicator::Renamed,
+        },
+    }
+}
+
+fn change_line(indicator: Indicator, path: String) -> Node {
+    block!(
+        spacer!(),
+        spacer!(),
+        Node::Indicator(indicator),
+        spacer!(),
+        text!(path)
+    )
+}
+
+/// Groups the change lines into the staged and unstaged sections, empty ones are left out.
+fn change_groups(staged: Vec<Node>, unstaged: Vec<Node>) -> Option<Node> {
+    let mut children = vec![];
+    let groups = [("Staged Changes", staged), ("Unstaged Changes", unstaged)];
+
+    for (name, lines) in groups {
+        if lines.is_empty() {
+            continue;
         }
 
         children.push(Node::Group(
             name.into(),
-            Some(count),
+            Some(lines.len()),
             Box::new(Node::MultiLine(lines)),
         ));
     }
 
-    if children.is_empty() {
-        return Ok(());
+    (!children.is_empty()).then_some(Node::MultiLine(children))
+}
+
+#[instrument(skip(ui, repo), ret(Debug))]
+fn render_changes(
+    ui: &mut impl Render,
+    repo: &Repository,
+    untracked: UntrackedFiles,
+) -> Result<(), Box<dyn Error>> {
+    let mut staged = vec![];
+    let mut unstaged = vec![];
+    let status = repo.status(progress::Discard)?.untracked_files(untracked);
+
+    // Only the rendered lines are kept, the status items are dropped as they arrive
+    for item in status.into_iter([])? {
+        let item = item?;
+        let line = change_line(indicator(&item), location(&item));
+
+        match item {
+            Item::TreeIndex(_) => staged.push(line),
+            Item::IndexWorktree(_) => unstaged.push(line),
+        }
     }
 
-    Ok(ui.render(&Node::MultiLine(children))?)
+    match change_groups(staged, unstaged) {
+        Some(node) => Ok(ui.render(&node)?),
+        None => Ok(()),
+    }
 }
 
 #[instrument(skip(repo), ret(Debug))]
-fn find_state(repo: &Repository) -> Result<Option<(gix::Id<'_>, gix::Id<'_>)>, Box<dyn Error>> {
+pub(crate) fn find_state(
+    repo: &Repository,
+) -> Result<Option<(gix::Id<'_>, gix::Id<'_>, String)>, Box<dyn Error>> {
     let Some(local) = repo.head_ref()? else {
         return Ok(None);
     };
@@ -324,19 +477,37 @@ fn find_state(repo: &Repository) -> Result<Option<(gix::Id<'_>, gix::Id<'_>)>, B
 
     let upstream = repo.find_reference(upstream.as_partial_name())?;
 
-    Ok(Some((local.id(), upstream.id())))
+    Ok(Some((
+        local.id(),
+        upstream.id(),
+        upstream.name().shorten().to_string(),
+    )))
 }
 
 #[instrument(skip(ui, repo), ret(Debug))]
-fn render(mut ui: impl Render, repo: Repository) -> Result<(), Box<dyn Error>> {
-    let graph = match find_state(&repo)? {
-        Some((local, remote)) => Some(Graph::ahead_behind(&repo, local, remote)?),
-        None => None,
+fn render(
+    mut ui: impl Render,
+    repo: Repository,
+    ahead_behind: bool,
+    untracked: UntrackedFiles,
+) -> Result<(), Box<dyn Error>> {
+    let state = find_state(&repo)?;
+    let graph = match &state {
+        Some((local, remote, _)) if ahead_behind => {
+            Some(Graph::ahead_behind(&repo, *local, *remote)?)
+        }
+        _ => None,
     };
+    let upstream = state.as_ref().map(|(_, _, name)| name.as_str());
 
-    render_branch(&mut ui, &repo, graph.as_ref())?;
+    // The header and the commit list share the same walk, only the counts are taken here
+    let counts = graph
+        .as_ref()
+        .map(|graph| (graph.ahead.len(), graph.behind.len()));
+
+    render_branch(&mut ui, &repo, upstream, counts)?;
     render_state(&mut ui, &repo)?;
-    render_changes(&mut ui, &repo)?;
+    render_changes(&mut ui, &repo, untracked)?;
 
     graph
         .map(|graph| {
@@ -348,16 +519,288 @@ fn render(mut ui: impl Render, repo: Repository) -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+/// Status codes of the index and worktree side, like `git status --porcelain`.
+fn status_codes(item: &Item) -> Option<(char, char)> {
+    Some(match item {
+        Item::TreeIndex(change) => match change {
+            gix::diff::index::ChangeRef::Addition { .. } => ('A', ' '),
+            gix::diff::index::ChangeRef::Deletion { .. } => ('D', ' '),
+            gix::diff::index::ChangeRef::Modification { .. } => ('M', ' '),
+            gix::diff::index::ChangeRef::Rewrite { .. } => ('R', ' '),
+        },
+        Item::IndexWorktree(item) => match item {
+            index_worktree::Item::Modification { status, .. } => match status {
+                EntryStatus::Conflict(_) => ('U', 'U'),
+                EntryStatus::Change(Change::Removed) => (' ', 'D'),
+                EntryStatus::Change(Change::Type { .. }) => (' ', 'T'),
+                EntryStatus::Change(_) => (' ', 'M'),
+                EntryStatus::IntentToAdd => (' ', 'A'),
+                EntryStatus::NeedsUpdate(_) => return None,
+            },
+            index_worktree::Item::DirectoryContents { entry, .. } => match entry.status {
+                gix::dir::entry::Status::Untracked => ('?', '?'),
+                _ => return None,
+            },
+            index_worktree::Item::Rewrite { .. } => (' ', 'R'),
+        },
+    })
+}
+
+/// Original path of a renamed or copied item.
+fn source_location(item: &Item) -> Option<String> {
+    match item {
+        Item::TreeIndex(gix::diff::index::ChangeRef::Rewrite {
+            source_location, ..
+        }) => Some(source_location.to_string()),
+        Item::IndexWorktree(index_worktree::Item::Rewrite { source, .. }) => {
+            Some(source.rela_path().to_string())
+        }
+        _ => None,
+    }
+}
+
+/// Index and worktree status codes per path, with the original path of renames.
+type Changes = BTreeMap<String, (char, char, Option<String>)>;
+
+fn collect_changes(
+    repo: &Repository,
+    untracked: UntrackedFiles,
+) -> Result<Changes, Box<dyn Error>> {
+    let mut changes = BTreeMap::new();
+    let status = repo.status(progress::Discard)?.untracked_files(untracked);
+
+    for item in status.into_iter([])? {
+        let item = item?;
+        let Some((index, worktree)) = status_codes(&item) else {
+            continue;
+        };
+
+        // Merge the staged and unstaged changes of a path into one entry
+        let entry = changes.entry(location(&item)).or_insert((' ', ' ', None));
+
+        if index != ' ' {
+            entry.0 = index;
+        }
+
+        if worktree != ' ' {
+            entry.1 = worktree;
+        }
+
+        if let Some(source) = source_location(&item) {
+            entry.2 = Some(source);
+        }
+    }
+
+    Ok(changes)
+}
+
+/// Formats a change like `git status --porcelain`: renames are shown as `old -> new`, or as the
+/// new path followed by the old one when NUL-terminated.
+fn porcelain_line(
+    path: &str,
+    index: char,
+    worktree: char,
+    source: Option<&str>,
+    null: bool,
+) -> String {
+    match (source, null) {
+        (Some(source), true) => format!("{index}{worktree} {path}\0{source}\0"),
+        (Some(source), false) => format!("{index}{worktree} {source} -> {path}\n"),
+        (None, true) => format!("{index}{worktree} {path}\0"),
+        (None, false) => format!("{index}{worktree} {path}\n"),
+    }
+}
+
+fn json_string(value: &str) -> String {
+    let mut output = String::from('"');
+
+    for c in value.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            c if c.is_control() => output.push_str(&format!("\\u{:04x}", c as u32)),
+            c => output.push(c),
+        }
+    }
+
+    output.push('"');
+    output
+}
+
+fn render_json(
+    repo: &Repository,
+    ahead_behind: bool,
+    untracked: UntrackedFiles,
+) -> Result<String, Box<dyn Error>> {
+    let head = repo.head()?;
+    let branch = match head
+        .referent_name()
+        .and_then(|name| name.category_and_short_name())
+    {
+        Some((Category::LocalBranch, name)) => json_string(&name.to_string()),
+        _ => "null".to_string(),
+    };
+    let upstream = match find_state(repo)? {
+        Some((local, remote, name)) if ahead_behind => {
+            let (ahead, behind) = Graph::counts(repo, local, remote)?;
+            format!(
+                "{{\"name\":{},\"ahead\":{ahead},\"behind\":{behind}}}",
+                json_string(&name)
+            )
+        }
+        Some((_, _, name)) => format!(
+            "{{\"name\":{},\"ahead\":null,\"behind\":null}}",
+            json_string(&name)
+        ),
+        None => "null".to_string(),
+    };
+    let files = collect_changes(repo, untracked)?
+        .into_iter()
+        .map(|(path, (index, worktree, _))| {
+            format!(
+                "{{\"path\":{},\"index\":\"{index}\",\"worktree\":\"{worktree}\"}}",
+                json_string(&path)
+            )
+        })
+        .collect::<Vec<_>>();
+
+    Ok(format!(
+        "{{\"branch\":{branch},\"upstream\":{upstream},\"files\":[{}]}}",
+        files.join(",")
+    ))
+}
+
 pub fn run(repo: Repository, opts: Opts) -> Result<(), Box<dyn Error>> {
-    if opts.no_pager {
-        render(TermRenderer::default(), repo)
+    let ahead_behind = opts
+        .ahead_behind
+        .or_else(|| repo.config_snapshot().boolean("status.aheadBehind"))
+        .unwrap_or(true);
+    let untracked = opts
+        .untracked_files
+        .map(UntrackedFiles::from)
+        .unwrap_or_else(|| untracked_files(&repo));
+
+    if opts.porcelain {
+        let null = term::null_terminated();
+
+        for (path, (index, worktree, source)) in collect_changes(&repo, untracked)? {
+            print!(
+                "{}",
+                porcelain_line(&path, index, worktree, source.as_deref(), null)
+            );
+        }
+
+        return Ok(());
+    }
+
+    if opts.json {
+        println!("{}", render_json(&repo, ahead_behind, untracked)?);
+        return Ok(());
+    }
+
+    if opts.no_pager || term::plumbing() {
+        render(
+            TermRenderer::default().with_width(term::width()),
+            repo,
+            ahead_behind,
+            untracked,
+        )
     } else {
-        let mut pager = Pager::new();
-        pager.set_prompt("status, q to quit")?;
+        let command = pager::configured(
+            repo.config_snapshot()
+                .string("core.pager")
+                .map(|pager| pager.to_string()),
+        );
+
+        Pager::new(command.as_deref(), "status, q to quit")?.page_all(|pager| {
+            render(
+                TermRenderer::new(pager).with_width(term::width()),
+                repo,
+                ahead_behind,
+                untracked,
+            )
+        })
+    }
+}
 
-        render(TermRenderer::new(&mut pager), repo)?;
-        minus::page_all(pager)?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{git::fixture, graph::WALKS};
+
+    fn render_plumbing(terminator: char) -> String {
+        let node = change_groups(
+            vec![change_line(Indicator::New, "tab\there".into())],
+            vec![
+                change_line(Indicator::Modified, "a".into()),
+                change_line(Indicator::Deleted, "dir/b".into()),
+            ],
+        )
+        .unwrap();
+        let mut ui = TermRenderer::new(String::new()).with_plumbing(Some(terminator));
+        ui.renderln(&node).unwrap();
+
+        ui.into_inner()
+    }
+
+    #[test]
+    fn test_ahead_behind_walks_once() {
+        // base -> local, base -> remote
+        let (_dir, git) = fixture::init();
+        let base = fixture::commit(&git, Some("HEAD"), &[], "base");
+        let remote = fixture::commit(&git, None, &[base], "remote");
+        fixture::commit(&git, Some("HEAD"), &[base], "local");
+
+        let branch = git.head().unwrap().shorthand().unwrap().to_string();
+        let mut config = git.config().unwrap();
+        git.remote("origin", "https://example.com/repo.git")
+            .unwrap();
+        git.reference(&format!("refs/remotes/origin/{branch}"), remote, false, "")
+            .unwrap();
+        config
+            .set_str(&format!("branch.{branch}.remote"), "origin")
+            .unwrap();
+        config
+            .set_str(
+                &format!("branch.{branch}.merge"),
+                &format!("refs/heads/{branch}"),
+            )
+            .unwrap();
+
+        let repo = gix::open(git.path()).unwrap();
+        let ui = TermRenderer::new(String::new());
+        WALKS.with(|walks| walks.set(0));
+        render(ui, repo, true, UntrackedFiles::None).unwrap();
+
+        // The header and the commit list share one ahead and one behind walk
+        assert_eq!(WALKS.with(|walks| walks.get()), 2);
+    }
+
+    #[test]
+    fn test_porcelain_rename() {
+        assert_eq!(
+            porcelain_line("new", 'R', ' ', Some("old"), false),
+            "R  old -> new\n"
+        );
+        assert_eq!(
+            porcelain_line("new", 'R', ' ', Some("old"), true),
+            "R  new\0old\0"
+        );
+        assert_eq!(porcelain_line("a", ' ', 'M', None, true), " M a\0");
+    }
 
-        Ok(())
+    #[test]
+    fn test_plumbing_changes() {
+        assert_eq!(
+            render_plumbing('\n'),
+            "Staged Changes\t1\nA\t\"tab\\there\"\nUnstaged Changes\t2\nM\ta\nD\tdir/b\n"
+        );
+        assert_eq!(
+            render_plumbing('\0'),
+            "Staged Changes\t1\0A\ttab\there\0Unstaged Changes\t2\0M\ta\0D\tdir/b\0"
+        );
     }
 }
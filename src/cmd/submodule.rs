@@ -0,0 +1,120 @@
+use std::error::Error;
+
+use clap::Parser;
+use gix::ObjectId;
+
+use crate::{
+    git::{Optional, RemoteOpts, Repo},
+    term::{
+        node::prelude::*,
+        render::{Render, TermRenderer},
+    },
+};
+
+#[derive(Parser)]
+#[clap(about = "Inspect and update submodules")]
+pub struct Opts {
+    #[clap(subcommand)]
+    cmd: Cmd,
+}
+
+#[derive(Parser)]
+pub enum Cmd {
+    #[clap(about = "Show the configured and checked out commit of each submodule")]
+    Status,
+
+    #[clap(about = "Clone missing submodules and check out the configured commits")]
+    Update {
+        #[clap(long, help = "Initialize submodules that aren't initialized yet")]
+        init: bool,
+    },
+}
+
+fn commit_short(oid: git2::Oid) -> Result<Node, Box<dyn Error>> {
+    Ok(Node::Attribute(Attribute::CommitShort(ObjectId::try_from(
+        oid.as_bytes(),
+    )?)))
+}
+
+fn submodule_status(repo: &Repo) -> Result<(), Box<dyn Error>> {
+    let mut ui = TermRenderer::default();
+
+    for submodule in repo.submodules()? {
+        let mut line = vec![
+            text!(submodule.path().display().to_string()),
+            spacer!(),
+            Node::Attribute(Attribute::Remote(
+                submodule.url().unwrap_or_default().to_string().into(),
+            )),
+            spacer!(),
+        ];
+
+        // The commit recorded in the superproject vs the one checked out in the submodule
+        let configured = submodule.index_id().or(submodule.head_id());
+
+        match (configured, submodule.workdir_id()) {
+            (Some(configured), Some(checked_out)) if configured == checked_out => {
+                line.push(commit_short(configured)?);
+            }
+            (Some(configured), Some(checked_out)) => {
+                line.push(commit_short(configured)?);
+                line.push(text!(" → "));
+                line.push(commit_short(checked_out)?.with_status(Status::Warning));
+            }
+            (Some(configured), None) => {
+                line.push(commit_short(configured)?);
+                line.push(spacer!());
+                line.push(dimmed!(text!("(not initialized)")));
+            }
+            (None, _) => line.push(dimmed!(text!("(not recorded)"))),
+        }
+
+        ui.renderln(&Node::Block(line))?;
+    }
+
+    Ok(())
+}
+
+fn update_submodules(repo: &Repo, init: bool) -> Result<(), Box<dyn Error>> {
+    let mut ui = TermRenderer::default();
+    let config = repo.config()?.snapshot()?;
+    let mut skipped = 0;
+
+    for mut submodule in repo.submodules()? {
+        let path = submodule.path().display().to_string();
+        let key = format!("submodule.{}.url", submodule.name().unwrap_or_default());
+
+        // Like git, uninitialized submodules are left alone unless --init is passed
+        if !init && config.get_string(&key).optional()?.is_none() {
+            ui.renderln(
+                &text!(format!("Skipped {path}, it isn't initialized"))
+                    .with_status(Status::Warning),
+            )?;
+            skipped += 1;
+            continue;
+        }
+
+        let mut opts = RemoteOpts::default();
+        let mut fetch = git2::FetchOptions::new();
+        fetch.remote_callbacks(opts.callbacks());
+
+        submodule.update(init, Some(git2::SubmoduleUpdateOptions::new().fetch(fetch)))?;
+
+        ui.renderln(&message_with_icon(Icon::Check, format!("Updated {path}")))?;
+    }
+
+    if skipped > 0 {
+        ui.renderln(&dimmed!(text!(
+            "Run 'src submodule update --init' to initialize them"
+        )))?;
+    }
+
+    Ok(())
+}
+
+pub fn run(repo: Repo, opts: Opts) -> Result<(), Box<dyn Error>> {
+    match opts.cmd {
+        Cmd::Status => submodule_status(&repo),
+        Cmd::Update { init } => update_submodules(&repo, init),
+    }
+}
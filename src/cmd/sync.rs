@@ -2,29 +2,121 @@ use std::error::Error;
 
 use clap::Parser;
 
-use crate::git::{Ref, RemoteOpts, Repo};
+use crate::{
+    cmd::checkout::try_checkout,
+    git::{Ref, RemoteOpts, Repo, StashError},
+    term::{
+        node::prelude::*,
+        render::{Render, TermRenderer},
+    },
+};
 
 #[derive(Parser)]
 #[clap(about = "Synchronize changes")]
 pub struct Opts {}
 
-pub fn run(repo: Repo, _opts: Opts) -> Result<(), Box<dyn Error>> {
+/// Picks the remote to sync with: the upstream of the current branch, the only remote or `origin`.
+fn remote_name(repo: &Repo) -> Result<String, Box<dyn Error>> {
+    let upstream = repo
+        .head()
+        .and_then(|head| head.into_branch())
+        .and_then(|branch| branch.upstream())
+        .ok();
+
+    if let Some(upstream) = upstream {
+        return Ok(upstream.remote_name()?.to_string());
+    }
+
+    let mut names = vec![];
+
+    for remote in repo.remotes()? {
+        if let Some(name) = remote?.name()? {
+            names.push(name.to_string());
+        }
+    }
+
+    match names.as_slice() {
+        [name] => Ok(name.clone()),
+        names if names.iter().any(|name| name == "origin") => Ok("origin".to_string()),
+        [] => Err("No remote found to sync with".into()),
+        names => Err(format!(
+            "Unable to determine the remote to sync with (found: {}), set an upstream for the \
+             current branch",
+            names.join(", ")
+        )
+        .into()),
+    }
+}
+
+/// Restores the changes stashed before syncing, the stash is kept when it conflicts.
+fn restore_stash(repo: &mut Repo, ui: &mut impl Render) -> Result<(), Box<dyn Error>> {
+    match repo.apply_stash(0) {
+        Ok(()) => {
+            repo.drop_stash(0)?;
+            ui.renderln(&message_with_icon(Icon::Check, "Stashed changes restored"))?;
+        }
+        Err(StashError::Conflict(paths)) => {
+            ui.renderln(
+                &text!(format!(
+                    "Stashed changes conflict with: {}, they're kept in stash@{{0}}",
+                    paths.join(", ")
+                ))
+                .with_status(Status::Warning),
+            )?;
+        }
+        Err(e) => return Err(e.into()),
+    }
+
+    Ok(())
+}
+
+pub fn run(mut repo: Repo, _opts: Opts) -> Result<(), Box<dyn Error>> {
     // Find remote default branch
     let refname = {
-        let mut remote = repo.find_remote("origin")?;
+        let mut remote = repo.find_remote(&remote_name(&repo)?)?;
         remote.connect(RemoteOpts::default())?;
         remote.default_branch()?
     };
 
-    let branch = refname.trim_start_matches("refs/heads/");
-
-    // Checkout local branch with the same name
-    let branch = repo.find_branch(branch)?;
-    let reference: Ref<'_> = branch.into();
+    let branch = refname.trim_start_matches("refs/heads/").to_string();
+    let mut ui = TermRenderer::default();
 
-    repo.checkout(&reference)?;
+    // Checkout local branch with the same name, stash local changes when they're in the way
+    let reference: Ref<'_> = repo.find_branch(&branch)?.into();
+    let stashed = !try_checkout(&repo, &reference)?;
     drop(reference);
 
-    // Pull the latest changes
-    super::pull::run(repo, super::pull::Opts::default())
+    if stashed {
+        repo.save_stash(&format!("auto stash before sync to: {branch}"))?;
+        ui.renderln(&message_with_icon(Icon::Check, "Changes stashed"))?;
+
+        let reference: Ref<'_> = repo.find_branch(&branch)?.into();
+        repo.checkout(&reference)?;
+    }
+
+    // Pull the latest changes, the stash is restored before the status is shown
+    let pulled = super::pull::pull(&repo, &super::pull::Opts::default());
+    let restored = if stashed {
+        restore_stash(&mut repo, &mut ui)
+    } else {
+        Ok(())
+    };
+
+    match (pulled, restored) {
+        (Ok(true), Ok(())) => {
+            super::status::run(gix::open(repo.path())?, super::status::Opts::default())
+        }
+        (Ok(false), Ok(())) => Ok(()),
+        (Err(e), Ok(())) | (Ok(_), Err(e)) => Err(e),
+        (Err(e), Err(restore)) => {
+            ui.renderln(
+                &text!(format!(
+                    "Failed to restore the stashed changes, they're kept in stash@{{0}}: {restore}"
+                ))
+                .with_status(Status::Warning),
+            )?;
+
+            Err(e)
+        }
+    }
 }
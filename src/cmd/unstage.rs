@@ -0,0 +1,53 @@
+use std::error::Error;
+
+use clap::{Parser, ValueHint};
+
+use crate::{
+    git::Repo,
+    term::{
+        node::prelude::*,
+        render::{Render, TermRenderer},
+        select::{self, SelectOpts},
+    },
+};
+
+#[derive(Parser)]
+#[clap(about = "Remove file contents from the index, keeping the changes")]
+pub struct Opts {
+    #[clap(value_hint = ValueHint::AnyPath)]
+    targets: Vec<String>,
+}
+
+pub fn run(repo: Repo, opts: Opts) -> Result<(), Box<dyn Error>> {
+    let targets = if opts.targets.is_empty() {
+        let files = repo
+            .status()?
+            .entries()
+            .filter(|entry| entry.is_staged())
+            .map(|entry| entry.path().map(|p| p.to_string()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let select_opts = SelectOpts::from_config(&repo.config()?, "unstage", "diff {} --staged")?;
+        select::multi(&files, &select_opts)?
+    } else {
+        opts.targets
+    };
+
+    if targets.is_empty() {
+        return Err("No targets specified".into());
+    }
+
+    repo.unstage(&targets)?;
+
+    let mut ui = TermRenderer::default();
+
+    for target in targets {
+        ui.renderln(&block!(
+            Node::Indicator(Indicator::Modified),
+            spacer!(),
+            text!(target)
+        ))?;
+    }
+
+    Ok(())
+}
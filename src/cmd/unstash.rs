@@ -7,18 +7,79 @@ use crate::{
     term::{
         node::prelude::*,
         render::{Render, TermRenderer},
+        select::{self, SelectOpts},
     },
 };
 
 #[derive(Parser)]
-#[clap(about = "Apply the changes from the last stash")]
+#[clap(about = "Apply the changes from a stash")]
 pub struct Opts {
-    #[clap(short = 'n', long, default_value = "0")]
-    index: usize,
+    #[clap(
+        short = 'n',
+        long,
+        help = "Stash index, picks one from the list when omitted"
+    )]
+    index: Option<usize>,
+
+    #[clap(short, long, help = "Apply the stash without dropping it")]
+    apply: bool,
+}
+
+/// One line per stash: `index\tshort id\tmessage`.
+fn stash_lines(repo: &mut Repo) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut lines = vec![];
+
+    for (index, stash) in repo.stashes()?.enumerate() {
+        let stash = stash?;
+        let id = stash.id().to_string();
+
+        lines.push(format!(
+            "{index}\t{}\t{}",
+            &id[..7],
+            stash
+                .message()
+                .unwrap_or_default()
+                .lines()
+                .next()
+                .unwrap_or_default()
+        ));
+    }
+
+    Ok(lines)
+}
+
+fn select_stash(repo: &mut Repo) -> Result<usize, Box<dyn Error>> {
+    let lines = stash_lines(repo)?;
+
+    if lines.is_empty() {
+        return Err("No stashes found".into());
+    }
+
+    let line = select::single(
+        &lines,
+        &SelectOpts::from_config(&repo.config()?, "unstash", "stash show --no-pager {1}")?
+            .with_delimiter("\t"),
+    )?
+    .ok_or("No stash selected")?;
+
+    Ok(line
+        .split('\t')
+        .next()
+        .and_then(|index| index.parse().ok())
+        .ok_or_else(|| format!("invalid selection: {line}"))?)
 }
 
 pub fn run(mut repo: Repo, opts: Opts) -> Result<(), Box<dyn Error>> {
-    repo.pop_stash(opts.index)?;
+    let index = match opts.index {
+        Some(index) => index,
+        None => select_stash(&mut repo)?,
+    };
+
+    if opts.apply {
+        repo.apply_stash(index)?;
+    } else {
+        repo.pop_stash(index)?;
+    }
 
     let mut term = TermRenderer::default();
     term.render(&message_with_icon(Icon::Check, "Changes applied"))?;
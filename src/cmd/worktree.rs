@@ -0,0 +1,112 @@
+use std::{error::Error, path::PathBuf};
+
+use clap::{Parser, ValueHint};
+use gix::ObjectId;
+
+use crate::{
+    git::{Optional, Repo},
+    term::{
+        node::prelude::*,
+        render::{Render, TermRenderer},
+    },
+};
+
+#[derive(Parser)]
+#[clap(about = "Manage multiple working trees")]
+pub struct Opts {
+    #[clap(subcommand)]
+    cmd: Cmd,
+}
+
+#[derive(Parser)]
+pub enum Cmd {
+    #[clap(about = "Create a worktree with a branch checked out")]
+    Add {
+        #[clap(value_hint = ValueHint::DirPath, help = "Path of the new worktree")]
+        path: PathBuf,
+
+        #[clap(help = "Branch to check out")]
+        branch: String,
+
+        #[clap(short, long, help = "Create the branch from HEAD if it doesn't exist")]
+        create: bool,
+    },
+
+    #[clap(about = "List the worktrees")]
+    List,
+
+    #[clap(about = "Remove a worktree and its directory")]
+    Remove {
+        #[clap(value_hint = ValueHint::DirPath, help = "Path of the worktree")]
+        path: PathBuf,
+
+        #[clap(
+            short,
+            long,
+            help = "Remove the worktree even if it has changes or is locked"
+        )]
+        force: bool,
+    },
+}
+
+fn add_worktree(
+    repo: &Repo,
+    path: PathBuf,
+    branch_name: &str,
+    create: bool,
+) -> Result<(), Box<dyn Error>> {
+    let branch = match repo.find_branch(branch_name).optional()? {
+        Some(branch) => branch,
+        None if create => repo.create_branch(branch_name, &repo.head()?.find_commit()?)?,
+        None => return Err(format!("Branch not found: {branch_name} (use --create)").into()),
+    };
+
+    repo.add_worktree(&path, &branch)?;
+
+    let mut ui = TermRenderer::default();
+    ui.renderln(&message_with_icon(
+        Icon::Check,
+        format!("Worktree created at {}", path.display()),
+    ))?;
+
+    Ok(())
+}
+
+fn list_worktrees(repo: &Repo) -> Result<(), Box<dyn Error>> {
+    let mut ui = TermRenderer::default();
+
+    for worktree in repo.worktrees()? {
+        let head = match (worktree.branch, worktree.commit) {
+            (Some(branch), _) => Node::Attribute(Attribute::Branch(branch.into())),
+            (None, Some(commit)) => Node::Attribute(Attribute::CommitShort(ObjectId::try_from(
+                commit.as_bytes(),
+            )?)),
+            (None, None) => dimmed!(text!("(missing)")),
+        };
+
+        ui.renderln(&block!(
+            text!(worktree.path.display().to_string()),
+            spacer!(),
+            head
+        ))?;
+    }
+
+    Ok(())
+}
+
+pub fn run(repo: Repo, opts: Opts) -> Result<(), Box<dyn Error>> {
+    match opts.cmd {
+        Cmd::Add {
+            path,
+            branch,
+            create,
+        } => add_worktree(&repo, path, &branch, create),
+        Cmd::List => list_worktrees(&repo),
+        Cmd::Remove { path, force } => {
+            repo.remove_worktree(&path, force)?;
+
+            let mut ui = TermRenderer::default();
+            Ok(ui.renderln(&message_with_icon(Icon::Check, "Worktree removed"))?)
+        }
+    }
+}
@@ -0,0 +1,193 @@
+use std::{error::Error, io};
+
+use git2::{ErrorClass, ErrorCode};
+
+use crate::{
+    git::{ConfigError, HookError, IndexError},
+    graph::GraphError,
+};
+
+/// Errors as reported by `main`, each kind has its own exit code so scripts can tell them apart.
+/// Commands return `Box<dyn Error>` and are classified here by their underlying error, see
+/// `is_gix` for the gitoxide errors that are recognized.
+#[derive(Debug, thiserror::Error)]
+pub enum AppError {
+    #[error(transparent)]
+    Git(git2::Error),
+    /// Failures while reading the repository through gitoxide.
+    #[error(transparent)]
+    Gix(Box<dyn Error>),
+    #[error(transparent)]
+    Config(ConfigError),
+    #[error(transparent)]
+    Io(io::Error),
+    #[error(transparent)]
+    Hook(HookError),
+    #[error("{0}")]
+    User(Box<dyn Error>),
+}
+
+impl AppError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::User(_) => 1,
+            Self::Io(_) => 74,
+            Self::Config(_) => 78,
+            Self::Git(_) | Self::Gix(_) => 128,
+            Self::Hook(e) => e.code,
+        }
+    }
+
+    pub fn hint(&self) -> Option<&'static str> {
+        match self {
+            Self::Git(e) if e.code() == ErrorCode::Locked => {
+                Some("another git process seems to be running, remove the lock file if it isn't")
+            }
+            Self::Git(e) if e.code() == ErrorCode::Auth => {
+                Some("check your credentials or SSH agent")
+            }
+            Self::Git(e) if matches!(e.class(), ErrorClass::Net | ErrorClass::Http) => {
+                Some("check your network connection")
+            }
+            Self::Config(_) => Some("check your configuration with 'src config list'"),
+            _ => None,
+        }
+    }
+}
+
+impl From<Box<dyn Error>> for AppError {
+    fn from(e: Box<dyn Error>) -> Self {
+        let e = match e.downcast::<git2::Error>() {
+            Ok(e) if e.class() == ErrorClass::Config => return Self::Config(ConfigError::Git(*e)),
+            Ok(e) => return Self::Git(*e),
+            Err(e) => e,
+        };
+
+        let e = match e.downcast::<IndexError>() {
+            Ok(e) => match *e {
+                IndexError::Git(e) => return Self::Git(e),
+                e => return Self::User(e.into()),
+            },
+            Err(e) => e,
+        };
+
+        let e = match e.downcast::<ConfigError>() {
+            Ok(e) => return Self::Config(*e),
+            Err(e) => e,
+        };
+
+        let e = match e.downcast::<HookError>() {
+            Ok(e) => return Self::Hook(*e),
+            Err(e) => e,
+        };
+
+        let e = match e.downcast::<io::Error>() {
+            Ok(e) => return Self::Io(*e),
+            Err(e) => e,
+        };
+
+        if is_gix(e.as_ref()) {
+            return Self::Gix(e);
+        }
+
+        // Command errors such as `CheckoutError::Git` wrap the libgit2 error as their source
+        match find_source::<git2::Error>(e.as_ref()) {
+            Some(source) => {
+                let git = git2::Error::new(source.code(), source.class(), e.to_string());
+
+                match git.class() {
+                    ErrorClass::Config => Self::Config(ConfigError::Git(git)),
+                    _ => Self::Git(git),
+                }
+            }
+            None => Self::User(e),
+        }
+    }
+}
+
+/// Whether the error or one of its sources comes from gitoxide. Its errors don't share a type, these
+/// are the ones commands run into while opening, walking and reading the status of a repository.
+fn is_gix(e: &(dyn Error + 'static)) -> bool {
+    macro_rules! any_source {
+        ($($ty:ty),* $(,)?) => {
+            false $(|| find_source::<$ty>(e).is_some())*
+        };
+    }
+
+    any_source!(
+        GraphError,
+        gix::open::Error,
+        gix::reference::find::Error,
+        gix::reference::find::existing::Error,
+        gix::reference::head_id::Error,
+        gix::reference::head_commit::Error,
+        gix::reference::iter::init::Error,
+        gix::head::peel::to_object::Error,
+        gix::object::find::existing::Error,
+        gix::object::try_into::Error,
+        gix::object::commit::Error,
+        gix::objs::decode::Error,
+        gix::repository::branch_remote_tracking_ref_name::Error,
+        gix::repository::merge_base::Error,
+        gix::revision::walk::Error,
+        gix::revision::walk::iter::Error,
+        gix::status::Error,
+        gix::status::into_iter::Error,
+        gix::status::iter::Error,
+    )
+}
+
+fn find_source<T: Error + 'static>(e: &(dyn Error + 'static)) -> Option<&T> {
+    let mut source = Some(e);
+
+    while let Some(e) = source {
+        if let Some(e) = e.downcast_ref::<T>() {
+            return Some(e);
+        }
+
+        source = e.source();
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exit_codes() {
+        let code = |e: Box<dyn Error>| AppError::from(e).exit_code();
+        let git = |class| git2::Error::new(ErrorCode::GenericError, class, "failed");
+
+        assert_eq!(code("Nothing to commit".into()), 1);
+        assert_eq!(code(git(ErrorClass::Reference).into()), 128);
+        assert_eq!(code(git(ErrorClass::Config).into()), 78);
+        assert_eq!(
+            code(crate::git::CheckoutError::Git(git(ErrorClass::Checkout)).into()),
+            128
+        );
+        assert_eq!(
+            code(crate::git::StashError::Git(git(ErrorClass::Config)).into()),
+            78
+        );
+        assert_eq!(code(crate::git::StashError::NotFound(3).into()), 1);
+        assert_eq!(code(io::Error::other("broken").into()), 74);
+
+        let (_dir, git) = crate::git::fixture::init();
+        let repo = gix::open(git.path()).unwrap();
+        let missing = repo.find_reference("refs/heads/missing").unwrap_err();
+        assert_eq!(code(missing.into()), 128);
+        assert_eq!(code(repo.head_commit().unwrap_err().into()), 128);
+        assert_eq!(
+            code(
+                HookError {
+                    name: "pre-commit".to_string(),
+                    code: 3
+                }
+                .into()
+            ),
+            3
+        );
+    }
+}
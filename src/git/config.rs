@@ -42,15 +42,31 @@ impl User {
     }
 }
 
+pub struct Core {
+    pub editor: Option<String>,
+}
+
 pub struct Push {
     pub auto_setup_remote: bool,
 }
 
+#[derive(Default)]
+pub struct MergeToolConfig {
+    pub cmd: Option<String>,
+}
+
+pub struct Merge {
+    pub tool: Option<String>,
+    pub tools: HashMap<String, MergeToolConfig>,
+}
+
 pub struct Config {
+    pub core: Core,
     pub commit: Commit,
     pub gpg: Gpg,
     pub user: User,
     pub push: Push,
+    pub merge: Merge,
 }
 
 impl Config {
@@ -90,11 +106,39 @@ fn parse_gpg_config(config: &git2::Config) -> Result<HashMap<String, GpgConfig>,
     Ok(gpg)
 }
 
+fn parse_mergetool_config(
+    config: &git2::Config,
+) -> Result<HashMap<String, MergeToolConfig>, Error> {
+    let mut tools = HashMap::new();
+    let mut entries = config.entries(Some("mergetool.*"))?;
+
+    while let Some(entry) = entries.next() {
+        let entry = entry?;
+        let name = std::str::from_utf8(entry.name_bytes())?;
+        let components = name.split('.').collect::<Vec<_>>();
+
+        if components.len() != 3 {
+            continue;
+        }
+
+        let value: &mut MergeToolConfig = tools.entry(components[1].to_string()).or_default();
+
+        if components[2] == "cmd" {
+            value.cmd = string(config, name)?
+        }
+    }
+
+    Ok(tools)
+}
+
 impl TryFrom<git2::Config> for Config {
     type Error = Error;
 
     fn try_from(config: git2::Config) -> Result<Self, Self::Error> {
         Ok(Self {
+            core: Core {
+                editor: string(&config, "core.editor")?,
+            },
             gpg: Gpg {
                 format: string(&config, "gpg.format")?
                     .map(|format| match format.as_str() {
@@ -115,6 +159,10 @@ impl TryFrom<git2::Config> for Config {
             push: Push {
                 auto_setup_remote: bool_or_default(&config, "push.autoSetupRemote")?,
             },
+            merge: Merge {
+                tool: string(&config, "merge.tool")?,
+                tools: parse_mergetool_config(&config)?,
+            },
         })
     }
 }
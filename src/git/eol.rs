@@ -0,0 +1,126 @@
+use super::Optional;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AutoCrlf {
+    True,
+    Input,
+    False,
+}
+
+impl AutoCrlf {
+    pub fn from_config(config: &git2::Config) -> Result<Self, git2::Error> {
+        Ok(
+            match config.get_string("core.autocrlf").optional()?.as_deref() {
+                Some("input") => AutoCrlf::Input,
+                Some(value) if git2::Config::parse_bool(value).unwrap_or(false) => AutoCrlf::True,
+                _ => AutoCrlf::False,
+            },
+        )
+    }
+}
+
+/// Line endings of text files in the working tree (`core.eol`), `native` is resolved to the
+/// platform's.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Eol {
+    Lf,
+    Crlf,
+}
+
+impl Eol {
+    pub fn from_config(config: &git2::Config) -> Result<Self, git2::Error> {
+        Ok(match config.get_string("core.eol").optional()?.as_deref() {
+            Some("crlf") => Eol::Crlf,
+            Some("lf") => Eol::Lf,
+            _ if cfg!(windows) => Eol::Crlf,
+            _ => Eol::Lf,
+        })
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum LineEndings {
+    None,
+    Lf,
+    Crlf,
+    Mixed,
+}
+
+impl LineEndings {
+    /// Detects the line endings used in `content`, binary content is reported as `None`.
+    pub fn detect(content: &[u8]) -> Self {
+        if content.contains(&0) {
+            return LineEndings::None;
+        }
+
+        let (mut lf, mut crlf) = (0, 0);
+
+        for (i, c) in content.iter().enumerate() {
+            if *c == b'\n' {
+                if i > 0 && content[i - 1] == b'\r' {
+                    crlf += 1;
+                } else {
+                    lf += 1;
+                }
+            }
+        }
+
+        match (lf, crlf) {
+            (0, 0) => LineEndings::None,
+            (_, 0) => LineEndings::Lf,
+            (0, _) => LineEndings::Crlf,
+            _ => LineEndings::Mixed,
+        }
+    }
+
+    /// Returns a warning if the line endings don't match the configured policy, `core.autocrlf`
+    /// takes precedence over `core.eol` like in git.
+    pub fn warning(&self, autocrlf: AutoCrlf, eol: Eol) -> Option<&'static str> {
+        match (self, autocrlf, eol) {
+            (LineEndings::Mixed, _, _) => Some("mixed line endings"),
+            (LineEndings::Crlf, AutoCrlf::True | AutoCrlf::Input, _) => {
+                Some("CRLF will be replaced by LF in the index")
+            }
+            (LineEndings::Lf, AutoCrlf::True, _)
+            | (LineEndings::Lf, AutoCrlf::False, Eol::Crlf) => {
+                Some("LF will be replaced by CRLF in the working tree")
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(LineEndings::detect(b"no newline"), LineEndings::None);
+        assert_eq!(LineEndings::detect(b"a\nb\n"), LineEndings::Lf);
+        assert_eq!(LineEndings::detect(b"a\r\nb\r\n"), LineEndings::Crlf);
+        assert_eq!(LineEndings::detect(b"a\r\nb\n"), LineEndings::Mixed);
+        assert_eq!(LineEndings::detect(b"a\0\r\nb\n"), LineEndings::None);
+    }
+
+    #[test]
+    fn test_warning() {
+        let warning =
+            |content: &[u8], autocrlf, eol| LineEndings::detect(content).warning(autocrlf, eol);
+
+        assert_eq!(warning(b"a\nb\n", AutoCrlf::False, Eol::Lf), None);
+        assert_eq!(
+            warning(b"a\nb\n", AutoCrlf::False, Eol::Crlf),
+            Some("LF will be replaced by CRLF in the working tree")
+        );
+        assert_eq!(warning(b"a\nb\n", AutoCrlf::Input, Eol::Crlf), None);
+        assert_eq!(
+            warning(b"a\r\nb\r\n", AutoCrlf::Input, Eol::Crlf),
+            Some("CRLF will be replaced by LF in the index")
+        );
+        assert_eq!(
+            warning(b"a\r\nb\n", AutoCrlf::False, Eol::Lf),
+            Some("mixed line endings")
+        );
+    }
+}
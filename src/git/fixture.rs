@@ -0,0 +1,87 @@
+//! Repositories for tests.
+
+use std::path::Path;
+
+use tempfile::TempDir;
+
+/// Initializes a repository with a repository-local identity, so tests don't depend on the
+/// global config of the machine they run on.
+pub fn init() -> (TempDir, git2::Repository) {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = git2::Repository::init(dir.path()).unwrap();
+    let mut config = repo
+        .config()
+        .unwrap()
+        .open_level(git2::ConfigLevel::Local)
+        .unwrap();
+
+    config.set_str("user.name", "src").unwrap();
+    config.set_str("user.email", "src@example.com").unwrap();
+    config.set_bool("commit.gpgsign", false).unwrap();
+
+    (dir, repo)
+}
+
+pub fn signature() -> git2::Signature<'static> {
+    git2::Signature::now("src", "src@example.com").unwrap()
+}
+
+/// Commits the current index on top of `parents`, `update_ref` (e.g. `HEAD`) is moved along.
+pub fn commit(
+    repo: &git2::Repository,
+    update_ref: Option<&str>,
+    parents: &[git2::Oid],
+    message: &str,
+) -> git2::Oid {
+    let signature = signature();
+    let tree = repo
+        .find_tree(repo.index().unwrap().write_tree().unwrap())
+        .unwrap();
+    let parents = parents
+        .iter()
+        .map(|oid| repo.find_commit(*oid).unwrap())
+        .collect::<Vec<_>>();
+
+    repo.commit(
+        update_ref,
+        &signature,
+        &signature,
+        message,
+        &tree,
+        &parents.iter().collect::<Vec<_>>(),
+    )
+    .unwrap()
+}
+
+/// Writes and stages `path`, then commits it on top of HEAD as `author`.
+pub fn commit_file(
+    repo: &git2::Repository,
+    path: &str,
+    content: &str,
+    message: &str,
+    author: &git2::Signature<'_>,
+) -> git2::Oid {
+    std::fs::write(repo.workdir().unwrap().join(path), content).unwrap();
+
+    let mut index = repo.index().unwrap();
+    index.add_path(Path::new(path)).unwrap();
+    index.write().unwrap();
+
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let parents = repo
+        .head()
+        .ok()
+        .map(|head| head.peel_to_commit().unwrap())
+        .into_iter()
+        .collect::<Vec<_>>();
+
+    repo.commit(
+        Some("HEAD"),
+        author,
+        author,
+        message,
+        &tree,
+        &parents.iter().collect::<Vec<_>>(),
+    )
+    .unwrap()
+}
@@ -0,0 +1,80 @@
+use std::{
+    io,
+    path::Path,
+    process::{Command, ExitStatus, Stdio},
+};
+
+#[derive(Debug, thiserror::Error)]
+#[error("{name} hook failed with exit code {code}")]
+pub struct HookError {
+    pub name: String,
+    pub code: i32,
+}
+
+pub struct HookResult {
+    pub name: String,
+    pub status: ExitStatus,
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+}
+
+impl HookResult {
+    pub fn success(&self) -> bool {
+        self.status.success()
+    }
+
+    pub fn output(&self) -> String {
+        let mut output = String::from_utf8_lossy(&self.stdout).to_string();
+        output.push_str(&String::from_utf8_lossy(&self.stderr));
+        output.trim_end().to_string()
+    }
+
+    pub fn into_error(self) -> HookError {
+        HookError {
+            name: self.name,
+            // Hooks killed by a signal don't have an exit code
+            code: self.status.code().unwrap_or(1),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    path.metadata()
+        .map(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+/// Runs the hook `name` from `hooks_dir` in `workdir`, returns `None` if the hook doesn't exist.
+pub fn run(
+    hooks_dir: &Path,
+    workdir: &Path,
+    name: &str,
+    args: &[&str],
+) -> Result<Option<HookResult>, io::Error> {
+    let path = hooks_dir.join(name);
+
+    if !is_executable(&path) {
+        return Ok(None);
+    }
+
+    let output = Command::new(&path)
+        .args(args)
+        .current_dir(workdir)
+        .stdin(Stdio::null())
+        .output()?;
+
+    Ok(Some(HookResult {
+        name: name.to_string(),
+        status: output.status,
+        stdout: output.stdout,
+        stderr: output.stderr,
+    }))
+}
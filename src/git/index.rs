@@ -1,9 +1,73 @@
-use std::path::Path;
+use std::{
+    path::{Path, PathBuf},
+    str::Utf8Error,
+};
 
-use git2::{IndexAddOption, IntoCString};
+use git2::{ErrorClass, ErrorCode, IndexAddOption, IndexConflict, IndexEntry};
+
+use super::pathspec;
+
+#[derive(Debug, thiserror::Error)]
+pub enum IndexError {
+    #[error("another git process seems to be running; {} exists", .0.display())]
+    Locked(PathBuf),
+    #[error("git error: {0}")]
+    Git(#[from] git2::Error),
+}
+
+impl IndexError {
+    /// Maps a failed index write, an `index.lock` held by another process gets a clear message.
+    pub fn from_write(index_path: Option<&Path>, e: git2::Error) -> Self {
+        let Some(path) = index_path else {
+            return Self::Git(e);
+        };
+
+        let mut lock = path.as_os_str().to_owned();
+        lock.push(".lock");
+        let lock = PathBuf::from(lock);
+
+        if e.code() == ErrorCode::Locked || (e.class() == ErrorClass::Index && lock.exists()) {
+            Self::Locked(lock)
+        } else {
+            Self::Git(e)
+        }
+    }
+}
 
 pub struct Index(git2::Index);
 
+#[derive(Clone, Copy)]
+pub enum Side {
+    Ours,
+    Theirs,
+}
+
+pub struct Conflict(pub IndexConflict);
+
+impl Conflict {
+    pub fn base(&self) -> Option<&IndexEntry> {
+        self.0.ancestor.as_ref()
+    }
+
+    pub fn side(&self, side: Side) -> Option<&IndexEntry> {
+        match side {
+            Side::Ours => self.0.our.as_ref(),
+            Side::Theirs => self.0.their.as_ref(),
+        }
+    }
+
+    pub fn path(&self) -> Result<&str, Utf8Error> {
+        let entry = self
+            .0
+            .our
+            .as_ref()
+            .or(self.0.their.as_ref())
+            .or(self.0.ancestor.as_ref());
+
+        std::str::from_utf8(entry.map(|e| e.path.as_slice()).unwrap_or_default())
+    }
+}
+
 impl From<git2::Index> for Index {
     fn from(index: git2::Index) -> Self {
         Self(index)
@@ -11,16 +75,24 @@ impl From<git2::Index> for Index {
 }
 
 impl Index {
+    /// Stages the paths matching `pathspecs`, ignored paths are only staged with `force`. Mode
+    /// changes count as modifications unless `core.fileMode` is false, symlinks are stored as
+    /// links unless `core.symlinks` is false.
     pub fn add(
         &mut self,
-        pathspecs: impl IntoIterator<Item = impl IntoCString>,
+        pathspecs: impl IntoIterator<Item = impl AsRef<str>>,
+        force: bool,
         mut callback: impl FnMut(&Path),
     ) -> Result<i32, git2::Error> {
         let mut count = 0;
 
         self.0.add_all(
-            pathspecs,
-            IndexAddOption::DEFAULT,
+            pathspec::normalize(pathspecs),
+            if force {
+                IndexAddOption::FORCE
+            } else {
+                IndexAddOption::DEFAULT
+            },
             Some(&mut |path, _| {
                 count += 1;
                 callback(path);
@@ -31,11 +103,95 @@ impl Index {
         Ok(count)
     }
 
-    pub fn write(&mut self) -> Result<(), git2::Error> {
-        self.0.write()
+    pub fn add_path(&mut self, path: &Path) -> Result<(), git2::Error> {
+        self.0.add_path(path)
+    }
+
+    pub fn remove_path(&mut self, path: &Path) -> Result<(), git2::Error> {
+        self.0.remove_path(path)
+    }
+
+    pub fn has_conflicts(&self) -> bool {
+        self.0.has_conflicts()
+    }
+
+    pub fn conflicts(&self) -> Result<Vec<Conflict>, git2::Error> {
+        self.0
+            .conflicts()?
+            .map(|conflict| conflict.map(Conflict))
+            .collect()
+    }
+
+    pub fn reload(&mut self) -> Result<(), git2::Error> {
+        self.0.read(false)
+    }
+
+    pub fn write(&mut self) -> Result<(), IndexError> {
+        self.0
+            .write()
+            .map_err(|e| IndexError::from_write(self.0.path(), e))
     }
 
     pub fn write_tree(&mut self) -> Result<git2::Oid, git2::Error> {
         self.0.write_tree()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::git::{fixture, Repo};
+
+    fn add(repo: &Repo, path: &str) -> u32 {
+        let mut index = repo.index().unwrap();
+        index.add([path], false, |_| {}).unwrap();
+        index.write().unwrap();
+
+        index.0.get_path(Path::new(path), 0).unwrap().mode
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_add_mode_and_symlink() {
+        use std::os::unix::fs::{symlink, PermissionsExt};
+
+        let (dir, repo) = fixture::init();
+        let repo = Repo::from(repo);
+        let script = dir.path().join("run.sh");
+
+        fs::write(&script, "#!/bin/sh\n").unwrap();
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o644)).unwrap();
+        assert_eq!(add(&repo, "run.sh"), 0o100644);
+
+        // A pure mode change is staged on its own
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+        assert_eq!(add(&repo, "run.sh"), 0o100755);
+
+        symlink("run.sh", dir.path().join("link")).unwrap();
+        assert_eq!(add(&repo, "link"), 0o120000);
+    }
+
+    #[test]
+    fn test_add_ignored() {
+        let (dir, repo) = fixture::init();
+        let repo = Repo::from(repo);
+
+        fs::write(dir.path().join(".gitignore"), "*.log\n").unwrap();
+        fs::write(dir.path().join("debug.log"), "trace\n").unwrap();
+        fs::write(dir.path().join("main.rs"), "fn main() {}\n").unwrap();
+
+        let paths = repo
+            .status()
+            .unwrap()
+            .entries()
+            .map(|entry| entry.path().unwrap().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(paths, vec![".gitignore", "main.rs"]);
+
+        let mut index = repo.index().unwrap();
+        assert_eq!(index.add(["debug.log"], false, |_| {}).unwrap(), 0);
+        assert_eq!(index.add(["debug.log"], true, |_| {}).unwrap(), 1);
+    }
+}
@@ -1,19 +1,27 @@
-use chrono::{DateTime, Local, TimeZone};
+use chrono::{DateTime, Duration, FixedOffset, Local, NaiveDate, NaiveDateTime, TimeZone};
 use git2::{Error, ErrorClass, ErrorCode};
 
 mod config;
+mod eol;
+#[cfg(test)]
+pub mod fixture;
+mod hooks;
 mod index;
 mod objects;
+pub mod pathspec;
 mod remote;
 mod repo;
 mod resolve;
 mod signer;
 mod status;
 
-pub use config::Config;
+pub use config::{Config, Error as ConfigError};
+pub use eol::LineEndings;
+pub use hooks::HookError;
+pub use index::{Conflict, Index, IndexError, Side};
 pub use objects::*;
-pub use remote::{ProgressEvent, RemoteOpts, SidebandOp};
-pub use repo::{CheckoutError, DiffOpts, Repo};
+pub use remote::{ProgressEvent, Rejection, RemoteOpts, SidebandOp, Update};
+pub use repo::{CheckoutError, DiffOpts, Repo, StashError, Whitespace, Worktree};
 pub use resolve::Pattern;
 
 pub trait Optional<T> {
@@ -35,9 +43,150 @@ impl<T> Optional<T> for Result<T, git2::Error> {
     }
 }
 
-pub fn parse_local_time(time: git2::Time) -> DateTime<Local> {
+/// Parses the time in the timezone it was recorded in.
+pub fn parse_time(time: git2::Time) -> DateTime<FixedOffset> {
+    let offset = FixedOffset::east_opt(time.offset_minutes() * 60)
+        .unwrap_or(FixedOffset::east_opt(0).unwrap());
+
     DateTime::from_timestamp(time.seconds(), 0)
-        .map(|dt| dt.naive_local())
-        .map(|dt| Local.from_utc_datetime(&dt))
+        .map(|dt| dt.with_timezone(&offset))
         .unwrap_or_default()
 }
+
+pub fn parse_local_time(time: git2::Time) -> DateTime<Local> {
+    parse_time(time).with_timezone(&Local)
+}
+
+fn parse_relative_date(input: &str) -> Option<DateTime<FixedOffset>> {
+    let input = input.strip_suffix("ago")?;
+    let mut parts = input.split(['.', ' ']).filter(|part| !part.is_empty());
+    let amount = parts.next()?.parse::<i64>().ok()?;
+    let unit = parts.next()?;
+    let seconds = match unit.strip_suffix('s').unwrap_or(unit) {
+        "second" => 1,
+        "minute" => 60,
+        "hour" => 60 * 60,
+        "day" => 24 * 60 * 60,
+        "week" => 7 * 24 * 60 * 60,
+        "month" => 30 * 24 * 60 * 60,
+        "year" => 365 * 24 * 60 * 60,
+        _ => return None,
+    };
+
+    if parts.next().is_some() {
+        return None;
+    }
+
+    Some((Local::now() - Duration::seconds(amount * seconds)).fixed_offset())
+}
+
+/// Parses ISO-8601 dates (`2024-01-31`, `2024-01-31 12:00`, `2024-01-31T12:00:00+01:00`), `now`
+/// and relative dates (`2.weeks.ago`, `3 days ago`), times without offset are in local time.
+pub fn parse_date(input: &str) -> Option<git2::Time> {
+    let input = input.trim();
+    let local = |dt: NaiveDateTime| Local.from_local_datetime(&dt).single();
+
+    let dt = if input == "now" {
+        Some(Local::now().fixed_offset())
+    } else if let Ok(dt) = DateTime::parse_from_rfc3339(input) {
+        Some(dt)
+    } else if let Ok(dt) = DateTime::parse_from_str(input, "%Y-%m-%d %H:%M:%S %z") {
+        Some(dt)
+    } else if let Some(dt) = ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(input, format).ok())
+    {
+        local(dt).map(|dt| dt.fixed_offset())
+    } else if let Ok(date) = NaiveDate::parse_from_str(input, "%Y-%m-%d") {
+        local(date.and_hms_opt(0, 0, 0)?).map(|dt| dt.fixed_offset())
+    } else {
+        parse_relative_date(input)
+    }?;
+
+    Some(git2::Time::new(
+        dt.timestamp(),
+        dt.offset().local_minus_utc() / 60,
+    ))
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+pub enum DateFormat {
+    Local,
+    Original,
+    IsoStrict,
+}
+
+impl DateFormat {
+    pub fn format(self, time: git2::Time) -> String {
+        match self {
+            DateFormat::Local => parse_local_time(time).format("%Y-%m-%d %H:%M").to_string(),
+            DateFormat::Original => parse_time(time).format("%Y-%m-%d %H:%M %z").to_string(),
+            DateFormat::IsoStrict => parse_time(time).to_rfc3339(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_time() {
+        let time = parse_time(git2::Time::new(1_700_000_000, 120));
+        assert_eq!(time.to_rfc3339(), "2023-11-15T00:13:20+02:00");
+        assert_eq!(time.timestamp(), 1_700_000_000);
+
+        let time = parse_time(git2::Time::new(1_700_000_000, -330));
+        assert_eq!(time.to_rfc3339(), "2023-11-14T16:43:20-05:30");
+    }
+
+    #[test]
+    fn test_parse_local_time() {
+        // The instant is preserved regardless of the offset it was recorded in
+        for offset in [-300, 0, 120, 540] {
+            let time = parse_local_time(git2::Time::new(1_700_000_000, offset));
+            assert_eq!(time.timestamp(), 1_700_000_000);
+            assert_eq!(
+                time.offset(),
+                Local.timestamp_opt(1_700_000_000, 0).unwrap().offset()
+            );
+        }
+    }
+
+    #[test]
+    fn test_date_format_keeps_offset() {
+        // 2023-11-14T22:13:20Z authored in UTC+02:00
+        let time = git2::Time::new(1_700_000_000, 120);
+
+        assert_eq!(DateFormat::Original.format(time), "2023-11-15 00:13 +0200");
+        assert_eq!(
+            DateFormat::IsoStrict.format(time),
+            "2023-11-15T00:13:20+02:00"
+        );
+
+        let time = git2::Time::new(1_700_000_000, -300);
+        assert_eq!(
+            DateFormat::IsoStrict.format(time),
+            "2023-11-14T17:13:20-05:00"
+        );
+    }
+
+    #[test]
+    fn test_parse_date() {
+        let time = parse_date("2023-11-15T00:13:20+02:00").unwrap();
+        assert_eq!(time.seconds(), 1_700_000_000);
+        assert_eq!(time.offset_minutes(), 120);
+
+        let time = parse_date("2023-11-15 00:13:20 +0200").unwrap();
+        assert_eq!(time.seconds(), 1_700_000_000);
+
+        let now = Local::now().timestamp();
+        let time = parse_date("2.days.ago").unwrap();
+        assert!((now - 2 * 24 * 60 * 60 - time.seconds()).abs() < 5);
+        assert!((now - parse_date("now").unwrap().seconds()).abs() < 5);
+
+        assert!(parse_date("3 hours ago").is_some());
+        assert!(parse_date("2024-01-31").is_some());
+        assert!(parse_date("yesterday-ish").is_none());
+    }
+}
@@ -1,8 +1,8 @@
 use std::str::Utf8Error;
 
-use chrono::{DateTime, Local};
 use git2::Signature;
 
+use super::DateFormat;
 use crate::term::node::{self, prelude::*};
 
 #[derive(Debug, thiserror::Error)]
@@ -15,6 +15,47 @@ pub enum Error {
     MissingRemote,
 }
 
+/// Splits a commit message into the subject and body at the first blank line.
+pub fn split_message(message: &str) -> (&str, &str) {
+    let message = message.trim();
+    let mut offset = 0;
+
+    for line in message.split_inclusive('\n') {
+        if line.trim().is_empty() {
+            return (message[..offset].trim_end(), message[offset..].trim());
+        }
+
+        offset += line.len();
+    }
+
+    (message, "")
+}
+
+/// Parses the `Key: value` trailers from the last paragraph of the body, the paragraph only counts
+/// as trailers when all of its lines are.
+fn parse_trailers(body: &str) -> Vec<(&str, &str)> {
+    let Some(paragraph) = body.trim_end().rsplit("\n\n").next() else {
+        return vec![];
+    };
+
+    paragraph
+        .lines()
+        .map(|line| {
+            line.split_once(':')
+                .filter(|(key, _)| {
+                    !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+                })
+                .map(|(key, value)| (key, value.trim()))
+        })
+        .collect::<Option<Vec<_>>>()
+        .unwrap_or_default()
+}
+
+/// Parses the trailers of a full commit message, the subject never counts as a trailer.
+pub fn message_trailers(message: &str) -> Vec<(&str, &str)> {
+    parse_trailers(split_message(message).1)
+}
+
 pub struct Tree<'a>(pub git2::Tree<'a>);
 
 impl<'a> From<git2::Tree<'a>> for Tree<'a> {
@@ -104,16 +145,13 @@ impl<'a> Commit<'a> {
         self.0.author()
     }
 
-    pub fn time(&self) -> DateTime<Local> {
-        super::parse_local_time(self.0.time())
+    pub fn time(&self) -> git2::Time {
+        self.0.time()
     }
 
-    pub fn headers_ui(&self) -> Node {
+    pub fn headers_ui(&self, date: DateFormat) -> Node {
         multi_line!(
-            node::column!(
-                text!("Date"),
-                text!(self.time().format("%Y-%m-%d %H:%M").to_string())
-            ),
+            node::column!(text!("Date"), text!(date.format(self.0.time()))),
             node::column!(text!("Author"), text!(self.author().to_string()))
         )
     }
@@ -122,6 +160,21 @@ impl<'a> Commit<'a> {
         std::str::from_utf8(self.0.message_bytes())
     }
 
+    /// First paragraph of the message.
+    pub fn subject(&self) -> Result<&str, Utf8Error> {
+        Ok(split_message(self.message()?).0)
+    }
+
+    /// Message without the subject, including any trailers.
+    pub fn body(&self) -> Result<&str, Utf8Error> {
+        Ok(split_message(self.message()?).1)
+    }
+
+    /// Trailers like `Signed-off-by: Name <email>` at the end of the body.
+    pub fn trailers(&self) -> Result<Vec<(&str, &str)>, Utf8Error> {
+        Ok(message_trailers(self.message()?))
+    }
+
     pub fn message_formatted(&self) -> String {
         self.message()
             .map(|msg| {
@@ -133,6 +186,10 @@ impl<'a> Commit<'a> {
             .unwrap_or_default()
     }
 
+    pub fn parent_ids(&self) -> Vec<git2::Oid> {
+        self.0.parent_ids().collect()
+    }
+
     pub fn parent(&self) -> Result<Option<Commit<'a>>, git2::Error> {
         self.parent_n(1)
     }
@@ -208,3 +265,37 @@ impl<'a> Ref<'a> {
         self.0.set_target(oid, message).map(Into::into)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_message() {
+        assert_eq!(split_message("Fix bug\n"), ("Fix bug", ""));
+        assert_eq!(
+            split_message("Fix bug\n\nIt was broken.\n\nReally.\n"),
+            ("Fix bug", "It was broken.\n\nReally.")
+        );
+        assert_eq!(
+            split_message("Fix bug\nin parser\n  \nBody"),
+            ("Fix bug\nin parser", "Body")
+        );
+    }
+
+    #[test]
+    fn test_parse_trailers() {
+        assert!(parse_trailers("").is_empty());
+        assert!(parse_trailers("It was broken.").is_empty());
+        assert!(parse_trailers("Note: this is prose, not a trailer\nsecond line").is_empty());
+        assert_eq!(
+            parse_trailers(
+                "It was broken.\n\nSigned-off-by: Jane <jane@example.com>\nFixes: #12\n"
+            ),
+            vec![
+                ("Signed-off-by", "Jane <jane@example.com>"),
+                ("Fixes", "#12")
+            ]
+        );
+    }
+}
@@ -0,0 +1,109 @@
+const EXCLUDE: [&str; 3] = [":(exclude)", ":!", ":^"];
+
+fn exclude(pathspec: &str) -> Option<(&str, &str)> {
+    EXCLUDE.iter().find_map(|magic| {
+        pathspec
+            .strip_prefix(magic)
+            .map(|pattern| (*magic, pattern))
+    })
+}
+
+/// Translates pathspecs into the form libgit2 understands.
+///
+/// libgit2 doesn't support the `:(exclude)` magic (or its `:!` / `:^` short forms) but it does
+/// support negative patterns with a leading `!`. As the first matching pattern wins, excludes are
+/// moved to the front and a match-all pattern is added if nothing else was included.
+pub fn normalize(pathspecs: impl IntoIterator<Item = impl AsRef<str>>) -> Vec<String> {
+    let mut excludes = vec![];
+    let mut includes = vec![];
+
+    for pathspec in pathspecs {
+        let pathspec = pathspec.as_ref();
+
+        match exclude(pathspec) {
+            Some((_, pattern)) => excludes.push(format!("!{pattern}")),
+            None => includes.push(pathspec.to_string()),
+        }
+    }
+
+    if !excludes.is_empty() && includes.is_empty() {
+        includes.push("*".to_string());
+    }
+
+    excludes.extend(includes);
+    excludes
+}
+
+/// Resolves pathspecs relative to the `prefix` directory and limits them to that directory.
+pub fn with_prefix(
+    pathspecs: impl IntoIterator<Item = impl AsRef<str>>,
+    prefix: &str,
+) -> Vec<String> {
+    let mut output = vec![];
+    let mut includes = false;
+
+    for pathspec in pathspecs {
+        let pathspec = pathspec.as_ref();
+
+        match exclude(pathspec) {
+            Some((magic, pattern)) => output.push(format!("{magic}{prefix}/{pattern}")),
+            None => {
+                includes = true;
+                output.push(format!("{prefix}/{pathspec}"));
+            }
+        }
+    }
+
+    if !includes {
+        output.push(prefix.to_string());
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use super::*;
+    use crate::git::{fixture, DiffOpts, Repo};
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize(["src"]), vec!["src"]);
+        assert_eq!(normalize([":!*.lock"]), vec!["!*.lock", "*"]);
+        assert_eq!(
+            normalize(["src", ":(exclude)*.lock", ":^target"]),
+            vec!["!*.lock", "!target", "src"]
+        );
+    }
+
+    #[test]
+    fn test_with_prefix() {
+        assert_eq!(with_prefix([] as [&str; 0], "src"), vec!["src"]);
+        assert_eq!(
+            with_prefix([":!*.lock"], "src"),
+            vec![":!src/*.lock", "src"]
+        );
+        assert_eq!(with_prefix(["main.rs"], "src"), vec!["src/main.rs"]);
+    }
+
+    #[test]
+    fn test_diff_exclude() {
+        let (dir, repo) = fixture::init();
+        let repo = Repo::from(repo);
+
+        fs::write(dir.path().join("main.rs"), "fn main() {}\n").unwrap();
+        fs::write(dir.path().join("Cargo.lock"), "version = 4\n").unwrap();
+
+        let diff = repo
+            .diff(DiffOpts::default().with_pathspec([":!*.lock"]))
+            .unwrap();
+        let paths = diff
+            .deltas()
+            .filter_map(|delta| delta.new_file().path().map(|p| p.to_path_buf()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(paths, vec![PathBuf::from("main.rs")]);
+    }
+}
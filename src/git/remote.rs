@@ -5,11 +5,15 @@ use std::{
     sync::mpsc::Sender,
 };
 
-use git2::{Cred, Direction, FetchOptions, Oid, PushOptions, RemoteCallbacks};
+use git2::{
+    AutotagOption, Cred, Direction, ErrorCode, FetchOptions, Oid, PushOptions, RemoteCallbacks,
+};
 use http::Uri;
 use regex::Regex;
 use ssh2_config::{ParseRule, SshConfig};
 
+use crate::term::{cooperate, interrupted};
+
 fn get_credentials(url: &str, username: Option<&str>) -> Result<Cred, git2::Error> {
     let mut username = username.unwrap_or_default().to_string();
 
@@ -56,13 +60,29 @@ fn parse_sideband_progress(re: &Regex, line: &[u8]) -> Option<(String, usize, us
     None
 }
 
-#[allow(dead_code)]
+const DRY_RUN: &str = "push aborted (dry run)";
+const INTERRUPTED: &str = "push aborted (interrupted)";
+const STALE_LEASE: &str = "update rejected (stale lease): the remote branch changed since it was \
+    last fetched, fetch to review the changes or use --force to overwrite them";
+
 pub struct Update {
     pub src: Oid,
     pub dst: Oid,
     pub refname: String,
 }
 
+pub struct Rejection {
+    pub update: Update,
+    pub reason: String,
+}
+
+impl Rejection {
+    /// Whether the remote has commits that aren't included in the push.
+    pub fn is_non_fast_forward(&self) -> bool {
+        self.reason.contains("non-fast-forward") || self.reason.contains("fetch first")
+    }
+}
+
 #[derive(Clone)]
 pub enum SidebandOp {
     Counting,
@@ -73,7 +93,7 @@ pub enum SidebandOp {
 #[derive(Clone)]
 pub enum ProgressEvent {
     Packing(usize, usize),
-    Transfer(usize, usize),
+    Transfer(usize, usize, usize),
     PushTransfer(usize, usize, usize),
     Sideband(SidebandOp, usize, usize),
 }
@@ -82,7 +102,10 @@ pub enum ProgressEvent {
 pub struct RemoteOpts {
     stdout: Vec<u8>,
     compare: Option<Oid>,
+    dry_run: bool,
     updates: Vec<Update>,
+    negotiated: Vec<Update>,
+    rejected: Vec<(String, String)>,
     tx: Option<Sender<ProgressEvent>>,
 }
 
@@ -92,17 +115,32 @@ impl RemoteOpts {
         self
     }
 
+    /// Rejects the push when the remote ref no longer points to `compare` (force-with-lease).
     pub fn with_compare(mut self, compare: Oid) -> Self {
         self.compare = Some(compare);
         self
     }
 
+    /// Aborts a push after negotiating, the reply contains the updates that would've been pushed.
+    pub fn with_dry_run(mut self) -> Self {
+        self.dry_run = true;
+        self
+    }
+
     pub fn callbacks(&mut self) -> RemoteCallbacks<'_> {
         let stdout = &mut self.stdout;
         let mut callbacks = RemoteCallbacks::new();
 
         callbacks.credentials(|url, username, _| get_credentials(url, username));
         callbacks.push_negotiation(|updates| {
+            if interrupted() {
+                return Err(git2::Error::new(
+                    git2::ErrorCode::User,
+                    git2::ErrorClass::None,
+                    INTERRUPTED,
+                ));
+            }
+
             if let Some(oid) = self.compare {
                 if !updates.iter().any(|upd| upd.src() == oid)
                     && !updates.iter().all(|upd| upd.src().is_zero())
@@ -110,11 +148,34 @@ impl RemoteOpts {
                     return Err(git2::Error::new(
                         git2::ErrorCode::User,
                         git2::ErrorClass::None,
-                        "update rejected (outdated)",
+                        STALE_LEASE,
                     ));
                 }
             }
 
+            self.negotiated.extend(updates.iter().map(|upd| Update {
+                src: upd.src(),
+                dst: upd.dst(),
+                refname: upd.dst_refname().unwrap_or_default().to_string(),
+            }));
+
+            if self.dry_run {
+                return Err(git2::Error::new(
+                    git2::ErrorCode::User,
+                    git2::ErrorClass::None,
+                    DRY_RUN,
+                ));
+            }
+
+            Ok(())
+        });
+
+        callbacks.push_update_reference(|refname, status| {
+            if let Some(reason) = status {
+                self.rejected
+                    .push((refname.to_string(), reason.to_string()));
+            }
+
             Ok(())
         });
 
@@ -147,9 +208,10 @@ impl RemoteOpts {
 
                     ctx.send(ProgressEvent::Sideband(op, current, total))
                         .is_ok()
+                        && !interrupted()
                 } else {
                     stdout.extend_from_slice(line);
-                    true
+                    !interrupted()
                 }
             });
 
@@ -166,28 +228,56 @@ impl RemoteOpts {
             let ctx = tx.clone();
             callbacks.transfer_progress(move |progress| {
                 ctx.send(ProgressEvent::Transfer(
+                    progress.received_bytes(),
                     progress.indexed_objects(),
                     progress.total_objects(),
                 ))
                 .is_ok()
+                    && !interrupted()
             });
+        } else {
+            // Returning false aborts the transfer
+            callbacks.transfer_progress(|_| !interrupted());
         }
 
         callbacks
     }
 
     pub fn into_reply(self) -> Reply {
+        let rejected = self
+            .rejected
+            .into_iter()
+            .map(|(refname, reason)| {
+                let update = self.negotiated.iter().find(|upd| upd.refname == refname);
+
+                Rejection {
+                    update: Update {
+                        src: update.map_or(Oid::zero(), |upd| upd.src),
+                        dst: update.map_or(Oid::zero(), |upd| upd.dst),
+                        refname,
+                    },
+                    reason,
+                }
+            })
+            .collect();
+
         Reply {
             stdout: self.stdout,
-            updates: self.updates,
+            updates: if self.dry_run {
+                self.negotiated
+            } else {
+                self.updates
+            },
+            rejected,
         }
     }
 }
 
 pub struct Reply {
     pub stdout: Vec<u8>,
-    #[allow(dead_code)]
     pub updates: Vec<Update>,
+    /// Refs the remote (or libgit2) refused to update.
+    pub rejected: Vec<Rejection>,
 }
 
 pub struct Remote<'a>(pub git2::Remote<'a>);
@@ -212,6 +302,7 @@ impl Remote<'_> {
     }
 
     pub fn fetch(&mut self, mut opts: RemoteOpts, refspec: &str) -> Result<Reply, git2::Error> {
+        let _cooperate = cooperate();
         let callbacks = opts.callbacks();
 
         self.0.fetch(
@@ -223,22 +314,55 @@ impl Remote<'_> {
         Ok(opts.into_reply())
     }
 
-    pub fn push(&mut self, mut opts: RemoteOpts, refspec: &str) -> Result<Reply, git2::Error> {
+    /// Fetches only into the destination of `refspec`, tags aren't followed and `FETCH_HEAD` isn't
+    /// written. Use an anonymous remote, a named one also updates its configured refs.
+    pub fn prefetch(&mut self, mut opts: RemoteOpts, refspec: &str) -> Result<Reply, git2::Error> {
+        let _cooperate = cooperate();
         let callbacks = opts.callbacks();
 
-        self.0.push(
+        self.0.fetch(
+            &[refspec],
+            Some(
+                FetchOptions::new()
+                    .remote_callbacks(callbacks)
+                    .download_tags(AutotagOption::None)
+                    .update_fetchhead(false),
+            ),
+            None,
+        )?;
+
+        Ok(opts.into_reply())
+    }
+
+    pub fn push(&mut self, mut opts: RemoteOpts, refspec: &str) -> Result<Reply, git2::Error> {
+        let _cooperate = cooperate();
+        let callbacks = opts.callbacks();
+        let result = self.0.push(
             &[refspec],
             Some(
                 PushOptions::new()
                     .remote_callbacks(callbacks)
                     .packbuilder_parallelism(0),
             ),
-        )?;
+        );
+
+        match result {
+            Err(e) if opts.dry_run && e.message() == DRY_RUN => {}
+            // libgit2 checks for non-fast-forward updates itself before sending anything
+            Err(e) if e.code() == ErrorCode::NotFastForward => {
+                for update in &opts.negotiated {
+                    opts.rejected
+                        .push((update.refname.clone(), "non-fast-forward".to_string()));
+                }
+            }
+            result => result?,
+        }
 
         Ok(opts.into_reply())
     }
 
     pub fn connect(&mut self, mut opts: RemoteOpts) -> Result<Reply, git2::Error> {
+        let _cooperate = cooperate();
         let callbacks = opts.callbacks();
         self.0
             .connect_auth(Direction::Fetch, Some(callbacks), None)?;
@@ -1,18 +1,34 @@
-use std::{borrow::Cow, error::Error, path::Path};
+use std::{
+    cell::Cell,
+    error::Error,
+    fs, io,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
 
 use git2::{
-    build::CheckoutBuilder, string_array::StringArray, BranchType, DiffFindOptions, DiffOptions,
-    ErrorClass, ErrorCode, StashApplyOptions, StashFlags, StatusOptions,
+    build::CheckoutBuilder, string_array::StringArray, ApplyLocation, ApplyOptions, BranchType,
+    CheckoutNotificationType, DiffFindOptions, DiffOptions, ErrorClass, ErrorCode, RepositoryState,
+    Sort, StashApplyOptions, StashFlags, StatusOptions,
 };
 
-use crate::git::signer::{ssh::SshSigner, Signer};
+use which::which;
+
+use crate::{
+    git::signer::{ssh::SshSigner, Signer},
+    rebase::{Rebase, RebaseOp, RebaseOperationType, REBASE_DIR},
+};
 
 use super::{
     config::Config,
-    index::Index,
+    eol::{AutoCrlf, Eol},
+    hooks::{self, HookResult},
+    index::{Index, IndexError},
     objects::{Branch, Commit, Ref, Tree},
+    pathspec,
     remote::Remote,
     status::Status,
+    Optional,
 };
 
 #[derive(Debug, thiserror::Error)]
@@ -20,7 +36,7 @@ pub enum CheckoutError {
     #[error("checkout results in conflict: {0}")]
     Conflict(git2::Error),
     #[error("git error: {0}")]
-    Git(git2::Error),
+    Git(#[source] git2::Error),
 }
 
 impl From<git2::Error> for CheckoutError {
@@ -38,6 +54,44 @@ pub enum StashError {
     Git(#[from] git2::Error),
     #[error("config error: {0}")]
     Config(#[from] super::config::Error),
+    #[error("stash conflicts with local changes in: {}", .0.join(", "))]
+    Conflict(Vec<String>),
+    #[error("stash not found: {0}")]
+    NotFound(usize),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum WorktreeError {
+    #[error("git error: {0}")]
+    Git(#[from] git2::Error),
+    #[error("worktree not found: {0}")]
+    NotFound(String),
+    #[error("worktree contains modified or untracked files (use --force to remove it anyway)")]
+    Dirty,
+}
+
+pub struct Worktree {
+    /// Name of a linked worktree, `None` for the main worktree.
+    pub name: Option<String>,
+    pub path: PathBuf,
+    pub branch: Option<String>,
+    pub commit: Option<git2::Oid>,
+}
+
+impl Worktree {
+    fn from_repo(name: Option<String>, repo: &git2::Repository) -> Result<Self, git2::Error> {
+        let head = repo.head().optional()?;
+
+        Ok(Self {
+            name,
+            path: repo.workdir().unwrap_or(repo.path()).to_path_buf(),
+            branch: head
+                .as_ref()
+                .filter(|head| head.is_branch())
+                .and_then(|head| head.shorthand().map(ToString::to_string)),
+            commit: head.and_then(|head| head.target()),
+        })
+    }
 }
 
 pub struct Remotes<'a> {
@@ -62,11 +116,17 @@ impl<'a> Iterator for Remotes<'a> {
 }
 
 enum DiffType<'a> {
+    Trees(Option<&'a Tree<'a>>, &'a Tree<'a>),
     All(&'a Tree<'a>),
     Staged(&'a Tree<'a>),
     Unstaged,
 }
 
+pub enum Whitespace {
+    Exact,
+    IgnoreAll,
+}
+
 pub struct DiffOpts<'a> {
     ty: DiffType<'a>,
     diff_opts: DiffOptions,
@@ -75,8 +135,7 @@ pub struct DiffOpts<'a> {
 impl Default for DiffOpts<'_> {
     fn default() -> Self {
         let mut opts = DiffOptions::new();
-        opts.force_text(true)
-            .ignore_whitespace(true)
+        opts.ignore_whitespace(true)
             .ignore_whitespace_change(false)
             .include_ignored(false)
             .include_untracked(true)
@@ -101,8 +160,36 @@ impl<'a> DiffOpts<'a> {
         self
     }
 
-    pub fn with_pathspec(mut self, pathspec: &str) -> Self {
-        self.diff_opts.pathspec(pathspec);
+    pub fn with_trees(mut self, old: &'a Tree<'a>, new: &'a Tree<'a>) -> Self {
+        self.ty = DiffType::Trees(Some(old), new);
+        self
+    }
+
+    pub fn with_whitespace(mut self, mode: Whitespace) -> Self {
+        let ignore = matches!(mode, Whitespace::IgnoreAll);
+
+        self.diff_opts
+            .ignore_whitespace(ignore)
+            .ignore_whitespace_change(false)
+            .ignore_whitespace_eol(false);
+        self
+    }
+
+    pub fn with_no_prefix(mut self) -> Self {
+        self.diff_opts.old_prefix("").new_prefix("");
+        self
+    }
+
+    pub fn with_context(mut self, lines: u32) -> Self {
+        self.diff_opts.context_lines(lines);
+        self
+    }
+
+    pub fn with_pathspec(mut self, pathspecs: impl IntoIterator<Item = impl AsRef<str>>) -> Self {
+        for pathspec in pathspec::normalize(pathspecs) {
+            self.diff_opts.pathspec(pathspec);
+        }
+
         self
     }
 }
@@ -122,6 +209,22 @@ impl Repo {
         self.repo.path()
     }
 
+    pub fn workdir(&self) -> Option<&Path> {
+        self.repo.workdir()
+    }
+
+    pub fn config(&self) -> Result<git2::Config, git2::Error> {
+        self.repo.config()
+    }
+
+    pub fn autocrlf(&self) -> Result<AutoCrlf, git2::Error> {
+        AutoCrlf::from_config(&self.repo.config()?)
+    }
+
+    pub fn eol(&self) -> Result<Eol, git2::Error> {
+        Eol::from_config(&self.repo.config()?)
+    }
+
     pub fn head(&self) -> Result<Ref<'_>, git2::Error> {
         self.repo.head().map(Into::into)
     }
@@ -130,6 +233,10 @@ impl Repo {
         self.repo.find_commit(oid).map(Into::into)
     }
 
+    pub fn find_commit_by_prefix(&self, prefix: &str) -> Result<Commit<'_>, git2::Error> {
+        self.repo.find_commit_by_prefix(prefix).map(Into::into)
+    }
+
     pub fn find_annotated_commit(
         &self,
         oid: git2::Oid,
@@ -144,14 +251,59 @@ impl Repo {
         self.repo.merge_analysis(&[commit])
     }
 
+    /// Merges `upstream` into HEAD and commits the result, returns `false` when there are
+    /// conflicts, leaving the repository in the merge state to resolve and commit them.
+    pub fn merge(
+        &self,
+        upstream: &git2::AnnotatedCommit,
+        message: &str,
+    ) -> Result<bool, Box<dyn Error>> {
+        let mut cb = CheckoutBuilder::default();
+        cb.safe().allow_conflicts(true).conflict_style_merge(true);
+
+        self.repo.merge(&[upstream], None, Some(&mut cb))?;
+
+        let mut index = self.repo.index()?;
+
+        if index.has_conflicts() {
+            return Ok(false);
+        }
+
+        let tree = self.find_tree(index.write_tree()?)?;
+        let oid = self.create_commit(&tree, message, None, None, None)?;
+
+        self.head()?.set_target(oid, &format!("merge: {message}"))?;
+        self.cleanup_merge()?;
+
+        Ok(true)
+    }
+
+    /// Removes the merge state (`MERGE_HEAD`, `MERGE_MSG`) after the merge was committed.
+    pub fn cleanup_merge(&self) -> Result<(), git2::Error> {
+        if self.repo.state() == RepositoryState::Merge {
+            self.repo.cleanup_state()?;
+        }
+
+        Ok(())
+    }
+
     pub fn find_tree(&self, oid: git2::Oid) -> Result<Tree<'_>, git2::Error> {
         self.repo.find_tree(oid).map(Into::into)
     }
 
+    pub fn find_blob(&self, oid: git2::Oid) -> Result<git2::Blob<'_>, git2::Error> {
+        self.repo.find_blob(oid)
+    }
+
     pub fn find_remote(&self, name: &str) -> Result<Remote<'_>, git2::Error> {
         self.repo.find_remote(name).map(Into::into)
     }
 
+    /// Creates an in-memory remote without any configured refspecs.
+    pub fn remote_anonymous(&self, url: &str) -> Result<Remote<'_>, git2::Error> {
+        self.repo.remote_anonymous(url).map(Into::into)
+    }
+
     pub fn find_branch(&self, name: &str) -> Result<Branch<'_>, git2::Error> {
         self.repo
             .find_branch(name, BranchType::Local)
@@ -176,6 +328,46 @@ impl Repo {
         self.repo.checkout_tree(tree.as_object(), Some(&mut cb))
     }
 
+    pub fn checkout_paths(
+        &self,
+        Tree(tree): &Tree<'_>,
+        paths: &[String],
+        force: bool,
+    ) -> Result<(), CheckoutError> {
+        // A safe checkout silently skips modified paths, so they are checked upfront instead
+        if !force {
+            let mut opts = StatusOptions::new();
+            opts.include_untracked(true);
+
+            for path in paths {
+                opts.pathspec(path);
+            }
+
+            let modified = self
+                .repo
+                .statuses(Some(&mut opts))?
+                .iter()
+                .any(|entry| !(entry.status() - git2::Status::WT_DELETED).is_empty());
+
+            if modified {
+                return Err(CheckoutError::Conflict(git2::Error::new(
+                    ErrorCode::Conflict,
+                    ErrorClass::Checkout,
+                    "paths have local modifications",
+                )));
+            }
+        }
+
+        let mut cb = CheckoutBuilder::default();
+        cb.force().recreate_missing(true);
+
+        for path in paths {
+            cb.path(path.as_str());
+        }
+
+        Ok(self.repo.checkout_tree(tree.as_object(), Some(&mut cb))?)
+    }
+
     pub fn checkout(&self, reference: &Ref<'_>) -> Result<(), CheckoutError> {
         let tree = reference.find_tree()?;
 
@@ -185,6 +377,15 @@ impl Repo {
         Ok(())
     }
 
+    pub fn checkout_detached(&self, commit: &Commit<'_>) -> Result<(), CheckoutError> {
+        let tree = commit.find_tree()?;
+
+        self.checkout_tree(&tree, false)?;
+        self.repo.set_head_detached(commit.id())?;
+
+        Ok(())
+    }
+
     pub fn branches(
         &self,
     ) -> Result<impl Iterator<Item = Result<Branch<'_>, git2::Error>> + '_, git2::Error> {
@@ -213,11 +414,149 @@ impl Repo {
         self.repo.branch(name, commit, false).map(Into::into)
     }
 
+    pub fn submodules(&self) -> Result<Vec<git2::Submodule<'_>>, git2::Error> {
+        self.repo.submodules()
+    }
+
+    /// Lists the main worktree followed by the linked worktrees.
+    pub fn worktrees(&self) -> Result<Vec<Worktree>, git2::Error> {
+        let mut worktrees = vec![];
+
+        if !self.repo.is_bare() {
+            let main = if self.repo.is_worktree() {
+                git2::Repository::open(self.repo.commondir())?
+            } else {
+                git2::Repository::open(self.repo.path())?
+            };
+
+            worktrees.push(Worktree::from_repo(None, &main)?);
+        }
+
+        for name in self.repo.worktrees()?.iter().flatten() {
+            let worktree = self.repo.find_worktree(name)?;
+
+            match git2::Repository::open_from_worktree(&worktree) {
+                Ok(repo) => worktrees.push(Worktree::from_repo(Some(name.to_string()), &repo)?),
+                // The directory was removed without pruning the worktree
+                Err(_) => worktrees.push(Worktree {
+                    name: Some(name.to_string()),
+                    path: worktree.path().to_path_buf(),
+                    branch: None,
+                    commit: None,
+                }),
+            }
+        }
+
+        Ok(worktrees)
+    }
+
+    /// Creates a worktree at `path` with `branch` checked out, named after the directory.
+    pub fn add_worktree(&self, path: &Path, branch: &Branch<'_>) -> Result<(), git2::Error> {
+        let name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| git2::Error::from_str("invalid worktree path"))?;
+        let reference = branch.0.get();
+
+        self.repo.worktree(
+            name,
+            path,
+            Some(git2::WorktreeAddOptions::new().reference(Some(reference))),
+        )?;
+
+        Ok(())
+    }
+
+    /// Removes the worktree at `path` along with its directory, refuses to remove worktrees with
+    /// changes unless `force` is set.
+    pub fn remove_worktree(&self, path: &Path, force: bool) -> Result<(), WorktreeError> {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let name = self
+            .worktrees()?
+            .into_iter()
+            .find(|worktree| {
+                worktree.path.canonicalize().ok().as_deref() == Some(path.as_path())
+                    || worktree.path == path
+            })
+            .and_then(|worktree| worktree.name)
+            .ok_or_else(|| WorktreeError::NotFound(path.display().to_string()))?;
+        let worktree = self.repo.find_worktree(&name)?;
+
+        if !force {
+            if let Ok(repo) = git2::Repository::open_from_worktree(&worktree) {
+                let statuses = repo.statuses(Some(
+                    StatusOptions::new()
+                        .include_untracked(true)
+                        .exclude_submodules(true),
+                ))?;
+
+                if !statuses.is_empty() {
+                    return Err(WorktreeError::Dirty);
+                }
+            }
+        }
+
+        worktree.prune(Some(
+            git2::WorktreePruneOptions::new()
+                .valid(true)
+                .locked(force)
+                .working_tree(true),
+        ))?;
+
+        Ok(())
+    }
+
+    pub fn delete_branch(&self, name: &str) -> Result<(), git2::Error> {
+        self.repo.find_branch(name, BranchType::Local)?.delete()
+    }
+
+    pub fn rename_branch(
+        &self,
+        old: &str,
+        new: &str,
+        force: bool,
+    ) -> Result<Branch<'_>, git2::Error> {
+        let mut branch = self.repo.find_branch(old, BranchType::Local)?;
+        let upstream = branch
+            .upstream()
+            .optional()?
+            .map(|upstream| {
+                upstream
+                    .name_bytes()
+                    .map(|name| String::from_utf8_lossy(name).to_string())
+            })
+            .transpose()?;
+        let is_head = branch.is_head();
+        let mut renamed = branch.rename(new, force)?;
+
+        if is_head {
+            self.repo.set_head(&format!("refs/heads/{new}"))?;
+        }
+
+        // Make sure the tracking configuration follows the branch
+        if let Some(upstream) = upstream {
+            if renamed.upstream().optional()?.is_none() {
+                renamed.set_upstream(Some(upstream.as_str()))?;
+            }
+        }
+
+        Ok(renamed.into())
+    }
+
+    pub fn ahead_behind(
+        &self,
+        local: git2::Oid,
+        upstream: git2::Oid,
+    ) -> Result<(usize, usize), git2::Error> {
+        self.repo.graph_ahead_behind(local, upstream)
+    }
+
     pub fn commits(
         &self,
         reference: &Ref<'_>,
     ) -> Result<impl Iterator<Item = Result<Commit<'_>, git2::Error>>, git2::Error> {
         let mut walker = self.repo.revwalk()?;
+        walker.set_sorting(Sort::TOPOLOGICAL | Sort::TIME)?;
         walker.push_ref(
             reference
                 .name()
@@ -250,6 +589,95 @@ impl Repo {
         )
     }
 
+    pub fn apply_stash(&mut self, index: usize) -> Result<(), StashError> {
+        let mut conflicts = vec![];
+        let result = {
+            let mut cb = CheckoutBuilder::default();
+            cb.safe()
+                .notify_on(CheckoutNotificationType::CONFLICT)
+                .notify(|_, path, _, _, _| {
+                    if let Some(path) = path {
+                        conflicts.push(path.display().to_string());
+                    }
+
+                    true
+                });
+
+            self.repo.stash_apply(
+                index,
+                Some(StashApplyOptions::default().checkout_options(cb)),
+            )
+        };
+
+        match result {
+            Err(e)
+                if matches!(e.code(), ErrorCode::Conflict | ErrorCode::MergeConflict)
+                    && !conflicts.is_empty() =>
+            {
+                Err(StashError::Conflict(conflicts))
+            }
+            result => Ok(result?),
+        }
+    }
+
+    pub fn drop_stash(&mut self, index: usize) -> Result<(), StashError> {
+        if index >= self.stashes()?.count() {
+            return Err(StashError::NotFound(index));
+        }
+
+        Ok(self.repo.stash_drop(index)?)
+    }
+
+    pub fn stash_diff(&mut self, index: usize) -> Result<git2::Diff<'_>, StashError> {
+        let oid = self
+            .stashes()?
+            .nth(index)
+            .transpose()?
+            .map(|commit| commit.id())
+            .ok_or(StashError::NotFound(index))?;
+        let commit = self.find_commit(oid)?;
+        let parent = commit.parent()?.ok_or(StashError::NotFound(index))?;
+        let (old, new) = (parent.find_tree()?, commit.find_tree()?);
+
+        Ok(self.diff(DiffOpts {
+            ty: DiffType::Trees(Some(&old), &new),
+            ..DiffOpts::default()
+        })?)
+    }
+
+    /// Diff of the changes introduced by `commit` compared to its first parent.
+    pub fn commit_diff(&self, commit: &Commit<'_>) -> Result<git2::Diff<'_>, git2::Error> {
+        let old = commit
+            .parent()?
+            .map(|parent| parent.find_tree())
+            .transpose()?;
+        let new = commit.find_tree()?;
+
+        self.diff(DiffOpts {
+            ty: DiffType::Trees(old.as_ref(), &new),
+            ..DiffOpts::default()
+        })
+    }
+
+    /// Returns true when the commit changes a path matching the pathspecs (compared to its first
+    /// parent).
+    pub fn touches(&self, commit: &Commit<'_>, pathspecs: &[String]) -> Result<bool, git2::Error> {
+        let old = commit
+            .parent()?
+            .map(|parent| parent.find_tree())
+            .transpose()?;
+        let new = commit.find_tree()?;
+        let diff = self.diff(
+            DiffOpts {
+                ty: DiffType::Trees(old.as_ref(), &new),
+                ..DiffOpts::default()
+            }
+            .with_pathspec(pathspecs),
+        )?;
+
+        Ok(diff.deltas().len() > 0)
+    }
+
     pub fn save_stash(&mut self, message: &str) -> Result<git2::Oid, StashError> {
         let config = Config::open_default()?;
         let signature = config.user.signature()?;
@@ -259,38 +687,403 @@ impl Repo {
             .stash_save(&signature, message, Some(StashFlags::INCLUDE_UNTRACKED))?)
     }
 
+    /// Creates a stash commit of the tracked changes without touching the working directory or
+    /// the stash ref, returns `None` when there is nothing to stash.
+    pub fn create_stash(&self, message: Option<&str>) -> Result<Option<git2::Oid>, StashError> {
+        let config = Config::open_default()?;
+        let signature = config.user.signature()?;
+        let head = self.repo.head()?;
+        let head_commit = head.peel_to_commit()?;
+        let branch = head.shorthand().unwrap_or("(no branch)");
+        let summary = format!(
+            "{branch}: {} {}",
+            &head_commit.id().to_string()[..7],
+            head_commit.summary().unwrap_or_default()
+        );
+
+        let mut index = self.repo.index()?;
+        let index_tree = index.write_tree()?;
+
+        // Stage tracked changes in memory to get the working directory tree, the index file
+        // itself is left alone and reloaded afterwards
+        index.update_all(["*"], None)?;
+        let workdir_tree = index.write_tree();
+        index.read(true)?;
+        let workdir_tree = workdir_tree?;
+
+        let head_tree = head_commit.tree_id();
+
+        if index_tree == head_tree && workdir_tree == head_tree {
+            return Ok(None);
+        }
+
+        let index_commit = self.repo.commit(
+            None,
+            &signature,
+            &signature,
+            &format!("index on {summary}"),
+            &self.repo.find_tree(index_tree)?,
+            &[&head_commit],
+        )?;
+        let message = match message {
+            Some(message) => format!("On {branch}: {message}"),
+            None => format!("WIP on {summary}"),
+        };
+
+        Ok(Some(self.repo.commit(
+            None,
+            &signature,
+            &signature,
+            &message,
+            &self.repo.find_tree(workdir_tree)?,
+            &[&head_commit, &self.repo.find_commit(index_commit)?],
+        )?))
+    }
+
+    /// Records an existing stash commit in the stash reflog.
+    pub fn store_stash(&self, oid: git2::Oid, message: &str) -> Result<(), git2::Error> {
+        self.repo.find_commit(oid)?;
+        self.repo.reference_ensure_log("refs/stash")?;
+        self.repo.reference("refs/stash", oid, true, message)?;
+
+        Ok(())
+    }
+
+    /// Lists the commits of HEAD that aren't in `upstream` as a rebase todo (oldest first), merge
+    /// commits are skipped like libgit2 does when rebasing.
+    pub fn rebase_todo(&self, upstream: git2::Oid) -> Result<Vec<RebaseOp>, Box<dyn Error>> {
+        let mut walker = self.repo.revwalk()?;
+        walker.set_sorting(Sort::TOPOLOGICAL | Sort::REVERSE)?;
+        walker.push_head()?;
+        walker.hide(upstream)?;
+
+        let mut todo = vec![];
+
+        for oid in walker {
+            let commit = self.repo.find_commit(oid?)?;
+
+            if commit.parent_count() > 1 {
+                continue;
+            }
+
+            todo.push(RebaseOp {
+                oid: gix::ObjectId::try_from(commit.id().as_bytes())?,
+                ty: RebaseOperationType::Pick,
+                message: commit.summary().unwrap_or_default().to_string(),
+            });
+        }
+
+        Ok(todo)
+    }
+
+    fn rebase_dir(&self) -> PathBuf {
+        self.path().join(REBASE_DIR)
+    }
+
+    fn read_rebase_file(&self, name: &str) -> io::Result<String> {
+        Ok(fs::read_to_string(self.rebase_dir().join(name))?
+            .trim()
+            .to_string())
+    }
+
+    fn write_rebase_todo(&self, todo: &[RebaseOp]) -> io::Result<()> {
+        fs::write(
+            self.rebase_dir().join("todo"),
+            todo.iter().map(|op| format!("{op}\n")).collect::<String>(),
+        )
+    }
+
+    fn conflicts(&self) -> Result<Vec<String>, Box<dyn Error>> {
+        let index = self.index()?;
+
+        if !index.has_conflicts() {
+            return Ok(vec![]);
+        }
+
+        Ok(index
+            .conflicts()?
+            .iter()
+            .map(|conflict| conflict.path().map(ToString::to_string))
+            .collect::<Result<Vec<_>, _>>()?)
+    }
+
+    /// Rebases HEAD onto `upstream` by applying the todo from top to bottom, commits missing from
+    /// the todo are dropped. `message` is used to edit the message of reworded and squashed
+    /// commits. Returns the conflicting paths when the rebase stopped.
+    pub fn rebase(
+        &self,
+        upstream: git2::Oid,
+        todo: &[RebaseOp],
+        message: &mut impl FnMut(&str) -> Result<String, Box<dyn Error>>,
+    ) -> Result<Vec<String>, Box<dyn Error>> {
+        if self.rebase_dir().exists() {
+            return Err("A rebase is already in progress, use --continue or --abort".into());
+        }
+
+        if let Some(op) = todo
+            .iter()
+            .find(|op| matches!(op.ty, RebaseOperationType::Edit | RebaseOperationType::Exec))
+        {
+            return Err(format!("unsupported rebase operation: {}", op.ty.name()).into());
+        }
+
+        let changed = self
+            .repo
+            .statuses(Some(StatusOptions::new().include_untracked(false)))?
+            .iter()
+            .any(|entry| entry.status() != git2::Status::CURRENT);
+
+        if changed {
+            return Err("Uncommitted changes, commit or stash them before rebasing".into());
+        }
+
+        let head = self.repo.head()?;
+        let onto = self.repo.find_commit(upstream)?;
+        let dir = self.rebase_dir();
+
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join("onto"), upstream.to_string())?;
+        fs::write(
+            dir.join("orig-head"),
+            head.peel_to_commit()?.id().to_string(),
+        )?;
+        fs::write(
+            dir.join("head-name"),
+            if head.is_branch() {
+                head.name().unwrap_or_default()
+            } else {
+                "detached HEAD"
+            },
+        )?;
+        self.write_rebase_todo(todo)?;
+
+        let mut cb = CheckoutBuilder::default();
+
+        if let Err(e) = self
+            .repo
+            .checkout_tree(onto.as_object(), Some(cb.safe()))
+            .and_then(|_| self.repo.set_head_detached(upstream))
+        {
+            fs::remove_dir_all(&dir)?;
+            return Err(e.into());
+        }
+
+        self.replay(todo, message)
+    }
+
+    /// Continues the rebase in progress once the conflicts are resolved and staged. Returns the
+    /// paths that are still conflicting.
+    pub fn continue_rebase(
+        &self,
+        message: &mut impl FnMut(&str) -> Result<String, Box<dyn Error>>,
+    ) -> Result<Vec<String>, Box<dyn Error>> {
+        if !self.rebase_dir().exists() {
+            return Err("No rebase in progress".into());
+        }
+
+        let conflicts = self.conflicts()?;
+
+        if !conflicts.is_empty() {
+            return Ok(conflicts);
+        }
+
+        let todo = Rebase::from_path(&self.rebase_dir().join("todo"))?.operations;
+
+        // The operation that stopped is first in the todo
+        if let Some((current, todo)) = todo.split_first() {
+            self.rebase_commit(current, message)?;
+            return self.replay(todo, message);
+        }
+
+        self.replay(&[], message)
+    }
+
+    pub fn abort_rebase(&self) -> Result<(), Box<dyn Error>> {
+        if !self.rebase_dir().exists() {
+            return Err("No rebase in progress".into());
+        }
+
+        let orig_head = git2::Oid::from_str(&self.read_rebase_file("orig-head")?)?;
+        let head_name = self.read_rebase_file("head-name")?;
+
+        if head_name.starts_with("refs/") {
+            self.repo.set_head(&head_name)?;
+        } else {
+            self.repo.set_head_detached(orig_head)?;
+        }
+
+        self.repo.reset(
+            self.repo.find_commit(orig_head)?.as_object(),
+            git2::ResetType::Hard,
+            None,
+        )?;
+
+        Ok(fs::remove_dir_all(self.rebase_dir())?)
+    }
+
+    /// Cherry-picks the remaining operations onto HEAD, stopping at the first conflict. The
+    /// rebased branch is updated once all operations are applied.
+    fn replay(
+        &self,
+        todo: &[RebaseOp],
+        message: &mut impl FnMut(&str) -> Result<String, Box<dyn Error>>,
+    ) -> Result<Vec<String>, Box<dyn Error>> {
+        for (i, op) in todo.iter().enumerate() {
+            if matches!(op.ty, RebaseOperationType::Drop) {
+                continue;
+            }
+
+            self.write_rebase_todo(&todo[i..])?;
+
+            let commit = self
+                .repo
+                .find_commit(git2::Oid::from_bytes(op.oid.as_bytes())?)?;
+
+            self.repo.cherrypick(&commit, None)?;
+            // The progress is tracked in the rebase todo instead
+            self.repo.cleanup_state()?;
+
+            let conflicts = self.conflicts()?;
+
+            if !conflicts.is_empty() {
+                return Ok(conflicts);
+            }
+
+            self.rebase_commit(op, message)?;
+        }
+
+        let head_name = self.read_rebase_file("head-name")?;
+
+        if head_name.starts_with("refs/") {
+            let head = self.repo.head()?.peel_to_commit()?;
+
+            self.repo
+                .reference(&head_name, head.id(), true, "rebase: finished")?;
+            self.repo.set_head(&head_name)?;
+        }
+
+        fs::remove_dir_all(self.rebase_dir())?;
+
+        Ok(vec![])
+    }
+
+    /// Commits the index for a rebase operation, squashes and fixups are folded into the previous
+    /// commit.
+    fn rebase_commit(
+        &self,
+        op: &RebaseOp,
+        message: &mut impl FnMut(&str) -> Result<String, Box<dyn Error>>,
+    ) -> Result<(), Box<dyn Error>> {
+        // The repository config takes precedence over the global one, like git does
+        let committer = self.repo.signature()?;
+        let commit = self
+            .repo
+            .find_commit(git2::Oid::from_bytes(op.oid.as_bytes())?)?;
+        let head = self.repo.head()?.peel_to_commit()?;
+        let tree = self.repo.find_tree(self.repo.index()?.write_tree()?)?;
+        let onto = git2::Oid::from_str(&self.read_rebase_file("onto")?)?;
+        // There is nothing to fold into when the first commit is a squash or fixup
+        let fold = head.id() != onto
+            && matches!(
+                op.ty,
+                RebaseOperationType::Squash | RebaseOperationType::Fixup
+            );
+
+        // The changes are already upstream
+        if !fold && tree.id() == head.tree_id() {
+            return Ok(());
+        }
+
+        let (new_message, author, parents) = match op.ty {
+            RebaseOperationType::Squash if fold => (
+                message(&format!(
+                    "{}\n\n{}",
+                    head.message().unwrap_or_default().trim_end(),
+                    commit.message().unwrap_or_default()
+                ))?,
+                head.author(),
+                head.parents().collect::<Vec<_>>(),
+            ),
+            RebaseOperationType::Fixup if fold => (
+                head.message().unwrap_or_default().to_string(),
+                head.author(),
+                head.parents().collect::<Vec<_>>(),
+            ),
+            RebaseOperationType::Reword => (
+                message(commit.message().unwrap_or_default())?,
+                commit.author(),
+                vec![head],
+            ),
+            _ => (
+                commit.message().unwrap_or_default().to_string(),
+                commit.author(),
+                vec![head],
+            ),
+        };
+
+        let oid = self.repo.commit(
+            None,
+            &author,
+            &committer,
+            &new_message,
+            &tree,
+            &parents.iter().collect::<Vec<_>>(),
+        )?;
+
+        Ok(self.repo.set_head_detached(oid)?)
+    }
+
     pub fn create_ref(&self, name: &str, target: git2::Oid) -> Result<Ref<'_>, git2::Error> {
         self.repo.reference(name, target, true, "").map(Into::into)
     }
 
+    /// Creates a commit without updating any refs, the parents default to HEAD (and the merged
+    /// commits when concluding a merge).
     pub fn create_commit(
         &self,
         tree: &Tree<'_>,
         message: &str,
-        parent: Option<&Commit<'_>>,
+        parents: Option<&[&Commit<'_>]>,
+        author: Option<&git2::Signature<'_>>,
+        sign: Option<bool>,
     ) -> Result<git2::Oid, Box<dyn Error>> {
         let config = Config::open_default()?;
-        let author = config.user.signature()?;
-        let parent_commit = match parent {
-            Some(parent) => Some(Cow::Borrowed(&parent.0)),
-            None => match self.repo.head() {
-                Ok(head) => Some(Cow::Owned(head.peel_to_commit()?)),
-                Err(e) if e.code() == ErrorCode::UnbornBranch => None,
+        let committer = config.user.signature()?;
+        let author = author.unwrap_or(&committer);
+        let mut owned = vec![];
+
+        if parents.is_none() {
+            match self.repo.head() {
+                Ok(head) => owned.push(head.peel_to_commit()?),
+                Err(e) if e.code() == ErrorCode::UnbornBranch => {}
                 Err(e) => return Err(e.into()),
-            },
+            }
+
+            if self.repo.state() == RepositoryState::Merge {
+                let mut oids = vec![];
+                self.repo.mergehead_foreach(|oid| {
+                    oids.push(*oid);
+                    true
+                })?;
+
+                for oid in oids {
+                    owned.push(self.repo.find_commit(oid)?);
+                }
+            }
+        }
+
+        let parents = match parents {
+            Some(parents) => parents.iter().map(|commit| &commit.0).collect::<Vec<_>>(),
+            None => owned.iter().collect(),
         };
-        let parents = parent_commit
-            .as_ref()
-            .map(|c| vec![c.as_ref()])
-            .unwrap_or_default();
 
-        if config.commit.gpg_sign {
+        if sign.unwrap_or(config.commit.gpg_sign) {
             match config.gpg.format {
                 Some(super::config::GpgFormat::Ssh) => {
                     let signer = SshSigner::from_config(&config)?;
                     let buf = self
                         .repo
-                        .commit_create_buffer(&author, &author, message, &tree.0, &parents)?;
+                        .commit_create_buffer(author, &committer, message, &tree.0, &parents)?;
                     let signed = signer.sign(&buf)?;
                     let content = std::str::from_utf8(&buf)?;
 
@@ -301,12 +1094,92 @@ impl Repo {
         } else {
             Ok(self
                 .repo
-                .commit(None, &author, &author, message, &tree.0, &parents)?)
+                .commit(None, author, &committer, message, &tree.0, &parents)?)
+        }
+    }
+
+    pub fn run_hook(
+        &self,
+        name: &str,
+        args: &[&str],
+    ) -> Result<Option<HookResult>, Box<dyn Error>> {
+        let workdir = self.repo.workdir().unwrap_or(self.repo.path());
+        let hooks_dir = match self.repo.config()?.get_path("core.hooksPath").optional()? {
+            Some(path) if path.is_relative() => workdir.join(path),
+            Some(path) => path,
+            None => self.repo.path().join("hooks"),
+        };
+
+        Ok(hooks::run(&hooks_dir, workdir, name, args)?)
+    }
+
+    /// Runs a git subcommand for operations libgit2 doesn't implement, returning its stdout.
+    fn git(&self, args: &[&str]) -> Result<String, Box<dyn Error>> {
+        let git = which("git").map_err(|_| "git executable not found")?;
+        let output = Command::new(git)
+            .arg("--git-dir")
+            .arg(self.repo.path())
+            .args(args)
+            .stderr(Stdio::inherit())
+            .output()?;
+
+        if !output.status.success() {
+            return Err(format!("git {} failed with {}", args.join(" "), output.status).into());
         }
+
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+
+    /// Writes the commit-graph with `git commit-graph write` as libgit2 can only read it, so this
+    /// needs git to be installed.
+    pub fn write_commit_graph(&self) -> Result<(), Box<dyn Error>> {
+        self.git(&["commit-graph", "write", "--reachable", "--changed-paths"])?;
+        Ok(())
+    }
+
+    pub fn pack_loose_objects(&self) -> Result<(), Box<dyn Error>> {
+        self.git(&["repack", "-d", "--quiet"])?;
+        Ok(())
+    }
+
+    fn reflog_entries(&self) -> Result<usize, git2::Error> {
+        let mut count = self.repo.reflog("HEAD")?.len();
+
+        for name in self.repo.references()?.names() {
+            count += self.repo.reflog(name?)?.len();
+        }
+
+        Ok(count)
+    }
+
+    /// Expires reflog entries older than `expire`, returns the number of removed entries.
+    pub fn expire_reflogs(&self, expire: &str) -> Result<usize, Box<dyn Error>> {
+        let before = self.reflog_entries()?;
+
+        self.git(&[
+            "reflog",
+            "expire",
+            "--all",
+            &format!("--expire={expire}"),
+            &format!("--expire-unreachable={expire}"),
+        ])?;
+
+        Ok(before.saturating_sub(self.reflog_entries()?))
+    }
+
+    /// Prunes unreachable loose objects older than `expire`, returns the number of removed objects.
+    pub fn prune(&self, expire: &str) -> Result<usize, Box<dyn Error>> {
+        let output = self.git(&["prune", "--verbose", &format!("--expire={expire}")])?;
+        Ok(output.lines().count())
     }
 
     pub fn diff(&self, mut opts: DiffOpts) -> Result<git2::Diff, git2::Error> {
         let mut diff = match opts.ty {
+            DiffType::Trees(old, new) => self.repo.diff_tree_to_tree(
+                old.map(|tree| &tree.0),
+                Some(&new.0),
+                Some(&mut opts.diff_opts),
+            )?,
             DiffType::All(tree) => self
                 .repo
                 .diff_tree_to_workdir_with_index(Some(&tree.0), Some(&mut opts.diff_opts))?,
@@ -325,6 +1198,54 @@ impl Repo {
         Ok(diff)
     }
 
+    /// Applies the selected hunks of `diff` to the index, `hunks[delta][hunk]` is true when the
+    /// hunk should be applied, missing entries are skipped.
+    pub fn apply_to_index(&self, diff: &git2::Diff, hunks: &[Vec<bool>]) -> Result<(), IndexError> {
+        let (delta, hunk) = (Cell::new(0), Cell::new(0));
+        let selected = |delta: usize, hunk: usize| {
+            hunks
+                .get(delta)
+                .and_then(|hunks| hunks.get(hunk))
+                .copied()
+                .unwrap_or(false)
+        };
+
+        let mut opts = ApplyOptions::new();
+        opts.delta_callback(|_| {
+            delta.set(delta.get() + 1);
+            hunk.set(0);
+
+            hunks
+                .get(delta.get() - 1)
+                .is_some_and(|hunks| hunks.contains(&true))
+        });
+        opts.hunk_callback(|_| {
+            hunk.set(hunk.get() + 1);
+            selected(delta.get() - 1, hunk.get() - 1)
+        });
+
+        self.repo
+            .apply(diff, ApplyLocation::Index, Some(&mut opts))
+            .map_err(|e| self.index_error(e))
+    }
+
+    fn index_error(&self, e: git2::Error) -> IndexError {
+        IndexError::from_write(Some(&self.path().join("index")), e)
+    }
+
+    /// Resets the index entries of `paths` to HEAD, leaving the working directory untouched.
+    pub fn unstage(&self, paths: &[String]) -> Result<(), IndexError> {
+        let head = match self.repo.head() {
+            Ok(head) => Some(head.peel(git2::ObjectType::Commit)?),
+            Err(e) if e.code() == ErrorCode::UnbornBranch => None,
+            Err(e) => return Err(e.into()),
+        };
+
+        self.repo
+            .reset_default(head.as_ref(), paths.iter().map(String::as_str))
+            .map_err(|e| self.index_error(e))
+    }
+
     pub fn index(&self) -> Result<Index, git2::Error> {
         self.repo.index().map(Into::into)
     }
@@ -343,3 +1264,319 @@ impl Repo {
         ))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::fixture::{self, commit_file};
+
+    #[test]
+    fn test_rename_branch_keeps_upstream() {
+        let (_dir, repo) = fixture::init();
+
+        {
+            let oid = fixture::commit(&repo, Some("HEAD"), &[], "init");
+            let commit = repo.find_commit(oid).unwrap();
+
+            repo.remote("origin", "https://example.com/repo.git")
+                .unwrap();
+            repo.reference("refs/remotes/origin/feature", oid, false, "")
+                .unwrap();
+            repo.branch("feature", &commit, false)
+                .unwrap()
+                .set_upstream(Some("origin/feature"))
+                .unwrap();
+        }
+
+        let repo = Repo::from(repo);
+        repo.rename_branch("feature", "renamed", false).unwrap();
+
+        let branch = repo.find_branch("renamed").unwrap();
+        assert_eq!(branch.upstream().unwrap().name().unwrap(), "origin/feature");
+        assert!(repo.find_branch("feature").optional().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_checkout_paths() {
+        let (dir, git) = fixture::init();
+        let signature = fixture::signature();
+
+        commit_file(&git, "a", "a\n", "add a", &signature);
+        commit_file(&git, "b", "b\n", "add b", &signature);
+        std::fs::write(dir.path().join("a"), "changed\n").unwrap();
+        std::fs::remove_file(dir.path().join("b")).unwrap();
+
+        let repo = Repo::from(git);
+        let tree = repo.head().unwrap().find_tree().unwrap();
+        let paths = ["a".to_string(), "b".to_string()];
+
+        assert!(matches!(
+            repo.checkout_paths(&tree, &paths, false),
+            Err(CheckoutError::Conflict(_))
+        ));
+        assert!(!dir.path().join("b").exists());
+
+        repo.checkout_paths(&tree, &paths[1..], false).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("b")).unwrap(),
+            "b\n"
+        );
+
+        std::fs::remove_file(dir.path().join("b")).unwrap();
+        repo.checkout_paths(&tree, &paths, true).unwrap();
+
+        for path in paths {
+            assert_eq!(
+                std::fs::read_to_string(dir.path().join(&path)).unwrap(),
+                format!("{path}\n")
+            );
+        }
+    }
+
+    /// Creates a repository with an `upstream` branch and returns its id, HEAD stays on the base
+    /// commit.
+    fn with_upstream(repo: &git2::Repository) -> git2::Oid {
+        let signature = fixture::signature();
+        let base = commit_file(repo, "a", "a\n", "base", &signature);
+        let upstream = commit_file(repo, "c", "c\n", "upstream", &signature);
+
+        repo.reference("refs/heads/upstream", upstream, false, "")
+            .unwrap();
+        repo.reset(
+            repo.find_commit(base).unwrap().as_object(),
+            git2::ResetType::Hard,
+            None,
+        )
+        .unwrap();
+
+        upstream
+    }
+
+    #[test]
+    fn test_rebase_fixup() {
+        let (dir, git) = fixture::init();
+        let signature = fixture::signature();
+        let upstream = with_upstream(&git);
+
+        commit_file(&git, "b", "b\n", "add b", &signature);
+        commit_file(&git, "b", "b\nfixed\n", "fix b", &signature);
+
+        let repo = Repo::from(git);
+        let mut todo = repo.rebase_todo(upstream).unwrap();
+        todo[1].ty = RebaseOperationType::Fixup;
+
+        let conflicts = repo
+            .rebase(upstream, &todo, &mut |message| Ok(message.to_string()))
+            .unwrap();
+        assert!(conflicts.is_empty());
+
+        let head = repo.head().unwrap().find_commit().unwrap();
+        assert_eq!(head.message().unwrap(), "add b");
+        assert_eq!(head.parent().unwrap().unwrap().id(), upstream);
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("b")).unwrap(),
+            "b\nfixed\n"
+        );
+    }
+
+    #[test]
+    fn test_rebase_reorder() {
+        let (_dir, git) = fixture::init();
+        let signature = fixture::signature();
+        let upstream = with_upstream(&git);
+
+        commit_file(&git, "b", "b\n", "add b", &signature);
+        commit_file(&git, "d", "d\n", "add d", &signature);
+
+        let repo = Repo::from(git);
+        let branch = repo.head().unwrap().name().unwrap().to_string();
+        let mut todo = repo.rebase_todo(upstream).unwrap();
+        todo.reverse();
+
+        let conflicts = repo
+            .rebase(upstream, &todo, &mut |message| Ok(message.to_string()))
+            .unwrap();
+        assert!(conflicts.is_empty());
+
+        let head = repo.head().unwrap().find_commit().unwrap();
+        let parent = head.parent().unwrap().unwrap();
+        assert_eq!(head.message().unwrap(), "add b");
+        assert_eq!(parent.message().unwrap(), "add d");
+        assert_eq!(parent.parent().unwrap().unwrap().id(), upstream);
+        assert_eq!(repo.head().unwrap().name().unwrap(), branch);
+    }
+
+    #[test]
+    fn test_rebase_drop_and_squash() {
+        let (dir, git) = fixture::init();
+        let signature = fixture::signature();
+        let upstream = with_upstream(&git);
+
+        commit_file(&git, "b", "b\n", "add b", &signature);
+        commit_file(&git, "d", "d\n", "add d", &signature);
+        commit_file(&git, "e", "e\n", "add e", &signature);
+
+        let repo = Repo::from(git);
+        let mut todo = repo.rebase_todo(upstream).unwrap();
+        todo[1].ty = RebaseOperationType::Drop;
+        todo[2].ty = RebaseOperationType::Squash;
+
+        let conflicts = repo
+            .rebase(upstream, &todo, &mut |message| Ok(message.to_string()))
+            .unwrap();
+        assert!(conflicts.is_empty());
+
+        let head = repo.head().unwrap().find_commit().unwrap();
+        assert_eq!(head.message().unwrap(), "add b\n\nadd e");
+        assert_eq!(head.parent().unwrap().unwrap().id(), upstream);
+        assert!(!dir.path().join("d").exists());
+        assert!(dir.path().join("e").exists());
+    }
+
+    #[test]
+    fn test_rebase_rejects_edit() {
+        let (_dir, git) = fixture::init();
+        let signature = fixture::signature();
+        let upstream = with_upstream(&git);
+
+        commit_file(&git, "b", "b\n", "add b", &signature);
+
+        let repo = Repo::from(git);
+        let mut todo = repo.rebase_todo(upstream).unwrap();
+        todo[0].ty = RebaseOperationType::Edit;
+
+        let err = repo
+            .rebase(upstream, &todo, &mut |message| Ok(message.to_string()))
+            .unwrap_err();
+        assert_eq!(err.to_string(), "unsupported rebase operation: edit");
+        assert!(!repo.path().join(REBASE_DIR).exists());
+    }
+
+    /// Starts a rebase of a local commit that conflicts with `upstream` on `c`.
+    fn conflicting_rebase(git: git2::Repository) -> (Repo, git2::Oid) {
+        let upstream = with_upstream(&git);
+        let local = commit_file(&git, "c", "local\n", "add c", &fixture::signature());
+
+        let repo = Repo::from(git);
+        let todo = repo.rebase_todo(upstream).unwrap();
+        let conflicts = repo
+            .rebase(upstream, &todo, &mut |message| Ok(message.to_string()))
+            .unwrap();
+        assert_eq!(conflicts, vec!["c"]);
+
+        (repo, local)
+    }
+
+    #[test]
+    fn test_rebase_continue() {
+        let (dir, git) = fixture::init();
+        let (repo, _) = conflicting_rebase(git);
+
+        assert_eq!(
+            repo.continue_rebase(&mut |message| Ok(message.to_string()))
+                .unwrap(),
+            vec!["c"]
+        );
+
+        std::fs::write(dir.path().join("c"), "resolved\n").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("c")).unwrap();
+        index.write().unwrap();
+
+        let conflicts = repo
+            .continue_rebase(&mut |message| Ok(message.to_string()))
+            .unwrap();
+        assert!(conflicts.is_empty());
+
+        let head = repo.head().unwrap();
+        assert!(head.0.is_branch());
+        assert_eq!(head.find_commit().unwrap().message().unwrap(), "add c");
+        assert!(!repo.path().join(REBASE_DIR).exists());
+    }
+
+    #[test]
+    fn test_rebase_abort() {
+        let (dir, git) = fixture::init();
+        let (repo, local) = conflicting_rebase(git);
+
+        repo.abort_rebase().unwrap();
+
+        let head = repo.head().unwrap();
+        assert!(head.0.is_branch());
+        assert_eq!(head.find_commit().unwrap().id(), local);
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("c")).unwrap(),
+            "local\n"
+        );
+        assert!(!repo.path().join(REBASE_DIR).exists());
+    }
+
+    #[test]
+    fn test_rebase_keeps_authors() {
+        let (_dir, git) = fixture::init();
+        let upstream = with_upstream(&git);
+        let alice =
+            git2::Signature::new("Alice", "alice@example.com", &git2::Time::new(0, 60)).unwrap();
+        let bob =
+            git2::Signature::new("Bob", "bob@example.com", &git2::Time::new(3600, -120)).unwrap();
+
+        commit_file(&git, "b", "b\n", "add b", &alice);
+        commit_file(&git, "d", "d\n", "add d", &bob);
+
+        let repo = Repo::from(git);
+        let todo = repo.rebase_todo(upstream).unwrap();
+
+        repo.rebase(upstream, &todo, &mut |message| Ok(message.to_string()))
+            .unwrap();
+
+        let head = repo.head().unwrap().find_commit().unwrap();
+        let parent = head.parent().unwrap().unwrap();
+
+        for (commit, author) in [(&head, &bob), (&parent, &alice)] {
+            assert_eq!(commit.author().name(), author.name());
+            assert_eq!(commit.author().email(), author.email());
+            assert_eq!(commit.author().when(), author.when());
+            assert_eq!(commit.0.committer().email(), Some("src@example.com"));
+        }
+
+        assert_eq!(parent.parent().unwrap().unwrap().id(), upstream);
+    }
+
+    #[test]
+    fn test_write_commit_graph() {
+        if which("git").is_err() {
+            return;
+        }
+
+        let (_dir, git) = fixture::init();
+        fixture::commit(&git, Some("HEAD"), &[], "init");
+
+        let repo = Repo::from(git);
+        repo.write_commit_graph().unwrap();
+
+        assert!(repo.path().join("objects/info/commit-graph").is_file());
+    }
+
+    #[test]
+    fn test_diff_respects_attributes() {
+        let (dir, repo) = fixture::init();
+        let repo = Repo::from(repo);
+
+        std::fs::write(dir.path().join(".gitattributes"), "*.min.js -diff\n").unwrap();
+        std::fs::write(dir.path().join("app.min.js"), "var a=1;\n").unwrap();
+
+        let diff = repo.diff(DiffOpts::default()).unwrap();
+        let mut binary = vec![];
+
+        diff.print(git2::DiffFormat::Patch, |delta, _hunk, line| {
+            if line.origin() == 'B' {
+                binary.push(delta.new_file().path().unwrap().to_path_buf());
+            }
+
+            true
+        })
+        .unwrap();
+
+        assert_eq!(binary, vec![Path::new("app.min.js")]);
+    }
+}
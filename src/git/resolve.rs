@@ -1,3 +1,4 @@
+use git2::ErrorCode;
 use nom::{
     branch::alt,
     bytes::complete::{tag, take_till},
@@ -28,6 +29,10 @@ fn prefix(pattern: &str) -> IResult<&str, Pattern<'_>> {
     }
 }
 
+fn is_commit_id(name: &str) -> bool {
+    name.len() >= 4 && name.len() <= 40 && name.chars().all(|c| c.is_ascii_hexdigit())
+}
+
 fn parent(pattern: &str) -> IResult<&str, Pattern<'_>> {
     let (input, (prefix, _, n)) = (prefix, tag("~"), i32).parse(pattern)?;
     Ok((input, Pattern::Parent((n as usize, Box::new(prefix)))))
@@ -42,7 +47,19 @@ impl<'a> Pattern<'a> {
     pub fn resolve(&self, repo: &Repo) -> Result<Option<git2::Oid>, git2::Error> {
         match self {
             Pattern::Head => Ok(Some(repo.head()?.target()?)),
-            Pattern::Branch(name) => repo.find_branch(name).and_then(|b| b.target()).optional(),
+            Pattern::Branch(name) => {
+                match repo.find_branch(name).and_then(|b| b.target()).optional()? {
+                    Some(oid) => Ok(Some(oid)),
+                    None if is_commit_id(name) => repo
+                        .find_commit_by_prefix(name)
+                        .map(|c| Some(c.id()))
+                        .or_else(|e| match e.code() {
+                            ErrorCode::NotFound => Ok(None),
+                            _ => Err(e),
+                        }),
+                    None => Ok(None),
+                }
+            }
             Pattern::Parent((n, pat)) => match pat.resolve(repo)? {
                 Some(oid) => Ok(repo.find_commit(oid)?.parent_n(*n)?.map(|c| c.id())),
                 None => Ok(None),
@@ -1,3 +1,6 @@
+//! Index status through git2 for the commands that stage and commit through git2 as well, the
+//! status overview itself (`cmd::status`) uses gix.
+
 use git2::{StatusEntry, Statuses};
 
 pub struct Status<'a>(pub Statuses<'a>);
@@ -19,6 +22,16 @@ impl Entry<'_> {
     pub fn path(&self) -> Result<&str, std::str::Utf8Error> {
         std::str::from_utf8(self.entry.path_bytes())
     }
+
+    pub fn is_staged(&self) -> bool {
+        self.entry.status().intersects(
+            git2::Status::INDEX_NEW
+                | git2::Status::INDEX_MODIFIED
+                | git2::Status::INDEX_DELETED
+                | git2::Status::INDEX_RENAMED
+                | git2::Status::INDEX_TYPECHANGE,
+        )
+    }
 }
 
 impl<'a> From<StatusEntry<'a>> for Entry<'a> {
@@ -1,4 +1,10 @@
-use gix::{revision::walk::Info, Repository};
+use std::{panic, thread};
+
+use gix::{ObjectId, Repository};
+
+/// Behind walks at least this long run alongside the ahead walk on a separate thread, below it
+/// spawning the thread costs more than it saves.
+const PARALLEL_THRESHOLD: usize = 1000;
 
 #[derive(Debug, thiserror::Error)]
 pub enum GraphError {
@@ -10,21 +16,35 @@ pub enum GraphError {
     Merge(#[from] gix::repository::merge_base::Error),
 }
 
-fn walk<'r>(
-    repo: &'r Repository,
-    base: gix::Id<'_>,
-    tip: gix::Id<'_>,
-) -> Result<Vec<Info<'r>>, GraphError> {
+#[cfg(test)]
+thread_local! {
+    /// Number of walks started on this thread, lets tests check how often the graph is walked.
+    pub static WALKS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+fn walk(
+    repo: &Repository,
+    base: ObjectId,
+    tip: ObjectId,
+) -> Result<impl Iterator<Item = Result<ObjectId, GraphError>> + '_, GraphError> {
+    #[cfg(test)]
+    WALKS.with(|walks| walks.set(walks.get() + 1));
+
     Ok(repo
         .rev_walk([tip])
         .with_pruned([base])
         .all()?
-        .collect::<Result<Vec<_>, _>>()?)
+        .map(|info| Ok(info?.id)))
+}
+
+/// Counts the commits reachable from `tip` but not from `base` without keeping them around.
+fn count(repo: &Repository, base: ObjectId, tip: ObjectId) -> Result<usize, GraphError> {
+    walk(repo, base, tip)?.try_fold(0, |count, id| id.map(|_| count + 1))
 }
 
 pub struct Graph<'r> {
-    pub ahead: Vec<Info<'r>>,
-    pub behind: Vec<Info<'r>>,
+    pub ahead: Vec<gix::Id<'r>>,
+    pub behind: Vec<gix::Id<'r>>,
 }
 
 impl<'r> Graph<'r> {
@@ -33,11 +53,243 @@ impl<'r> Graph<'r> {
         left: gix::Id<'_>,
         right: gix::Id<'_>,
     ) -> Result<Graph<'r>, GraphError> {
-        let merge_base = repo.merge_base(left, right)?;
+        let merge_base = repo.merge_base(left, right)?.detach();
+        let (left, right) = (left.detach(), right.detach());
+
+        let mut behind_walk = walk(repo, merge_base, right)?;
+        let mut behind = behind_walk
+            .by_ref()
+            .take(PARALLEL_THRESHOLD)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let ahead = if behind.len() < PARALLEL_THRESHOLD {
+            walk(repo, merge_base, left)?.collect::<Result<Vec<_>, _>>()?
+        } else {
+            // Repositories aren't `Sync`, the other thread walks with its own handle
+            let sync = repo.clone().into_sync();
+
+            thread::scope(|scope| {
+                let handle = scope.spawn(move || {
+                    walk(&sync.to_thread_local(), merge_base, left)?.collect::<Result<Vec<_>, _>>()
+                });
+
+                for id in behind_walk {
+                    behind.push(id?);
+                }
+
+                handle.join().unwrap_or_else(|e| panic::resume_unwind(e))
+            })?
+        };
 
         Ok(Graph {
-            ahead: walk(repo, merge_base, left)?,
-            behind: walk(repo, merge_base, right)?,
+            ahead: ahead.into_iter().map(|id| id.attach(repo)).collect(),
+            behind: behind.into_iter().map(|id| id.attach(repo)).collect(),
         })
     }
+
+    /// Like [`Graph::ahead_behind`] but only returns the number of commits ahead and behind.
+    pub fn counts(
+        repo: &Repository,
+        left: gix::Id<'_>,
+        right: gix::Id<'_>,
+    ) -> Result<(usize, usize), GraphError> {
+        let merge_base = repo.merge_base(left, right)?.detach();
+
+        Ok((
+            count(repo, merge_base, left.detach())?,
+            count(repo, merge_base, right.detach())?,
+        ))
+    }
+}
+
+/// Tracks the lanes of an ASCII commit graph, each lane waits for the commit it leads to.
+pub struct Lanes<T> {
+    lanes: Vec<Option<T>>,
+}
+
+impl<T> Default for Lanes<T> {
+    fn default() -> Self {
+        Self { lanes: vec![] }
+    }
+}
+
+impl<T: PartialEq + Clone> Lanes<T> {
+    fn row(&self, edges: &[(usize, char)], commit: Option<usize>) -> String {
+        let mut row = self
+            .lanes
+            .iter()
+            .enumerate()
+            .flat_map(|(i, lane)| {
+                let c = if Some(i) == commit {
+                    '*'
+                } else if lane.is_some() {
+                    '|'
+                } else {
+                    ' '
+                };
+
+                [c, ' ']
+            })
+            .collect::<Vec<_>>();
+
+        for &(pos, c) in edges {
+            if pos >= row.len() {
+                row.resize(pos + 1, ' ');
+            }
+
+            row[pos] = c;
+
+            // The lane the edge leads into/comes from is drawn by the edge itself
+            if pos + 1 < row.len() {
+                row[pos + 1] = ' ';
+            }
+        }
+
+        row.into_iter().collect::<String>().trim_end().to_string()
+    }
+
+    /// Prefix for lines shown below a commit.
+    pub fn padding(&self) -> String {
+        self.row(&[], None)
+    }
+
+    /// Places the commit in a lane, returns the commit row followed by the rows with merge (`\`)
+    /// and converging (`/`) edges.
+    pub fn next(&mut self, id: &T, parents: &[T]) -> Vec<String> {
+        let col = match self.lanes.iter().position(|lane| lane.as_ref() == Some(id)) {
+            Some(col) => col,
+            None => match self.lanes.iter().position(Option::is_none) {
+                Some(col) => col,
+                None => {
+                    self.lanes.push(None);
+                    self.lanes.len() - 1
+                }
+            },
+        };
+
+        let mut rows = vec![self.row(&[], Some(col))];
+        let mut converged = (col + 1..self.lanes.len())
+            .filter(|&i| self.lanes[i].as_ref() == Some(id))
+            .collect::<Vec<_>>();
+
+        for &i in &converged {
+            self.lanes[i] = None;
+        }
+
+        // Join a lane on the left when it already waits for the first parent
+        let first = parents.first().cloned();
+        let joined = first
+            .as_ref()
+            .and_then(|parent| (0..col).find(|&i| self.lanes[i].as_ref() == Some(parent)));
+
+        self.lanes[col] = match joined {
+            Some(_) => {
+                converged.insert(0, col);
+                None
+            }
+            None => first,
+        };
+
+        let mut branched = vec![];
+
+        for parent in parents.iter().skip(1) {
+            if self.lanes.iter().any(|lane| lane.as_ref() == Some(parent)) {
+                continue;
+            }
+
+            let lane = match (col + 1..self.lanes.len()).find(|&i| self.lanes[i].is_none()) {
+                Some(lane) => lane,
+                None => {
+                    self.lanes.push(None);
+                    self.lanes.len() - 1
+                }
+            };
+
+            self.lanes[lane] = Some(parent.clone());
+            branched.push(lane);
+        }
+
+        if !converged.is_empty() {
+            let edges = converged
+                .iter()
+                .map(|&i| (i * 2 - 1, '/'))
+                .collect::<Vec<_>>();
+            rows.push(self.row(&edges, None));
+        }
+
+        if !branched.is_empty() {
+            let edges = branched
+                .iter()
+                .map(|&i| (i * 2 - 1, '\\'))
+                .collect::<Vec<_>>();
+            rows.push(self.row(&edges, None));
+        }
+
+        while self.lanes.last().is_some_and(Option::is_none) {
+            self.lanes.pop();
+        }
+
+        rows
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::fixture;
+
+    fn ahead_behind(git: &git2::Repository, local: git2::Oid, remote: git2::Oid) -> [usize; 4] {
+        let repo = gix::open(git.path()).unwrap();
+        let id = |oid: git2::Oid| {
+            gix::ObjectId::try_from(oid.as_bytes())
+                .unwrap()
+                .attach(&repo)
+        };
+        let (local, remote) = (id(local), id(remote));
+        let graph = Graph::ahead_behind(&repo, local, remote).unwrap();
+        let (ahead, behind) = Graph::counts(&repo, local, remote).unwrap();
+
+        [graph.ahead.len(), graph.behind.len(), ahead, behind]
+    }
+
+    #[test]
+    fn test_counts_match_walk() {
+        // base -> a1 -> a2 (local), base -> b1 (remote)
+        let (_dir, git) = fixture::init();
+        let base = fixture::commit(&git, None, &[], "base");
+        let a1 = fixture::commit(&git, None, &[base], "a1");
+        let local = fixture::commit(&git, None, &[a1], "a2");
+        let remote = fixture::commit(&git, None, &[base], "b1");
+
+        assert_eq!(ahead_behind(&git, local, remote), [2, 1, 2, 1]);
+    }
+
+    #[test]
+    fn test_counts_with_merge() {
+        // base -> a1 -> merge (of a1 and b1) -> a2 (local), base -> b1 -> b2 (remote)
+        let (_dir, git) = fixture::init();
+        let base = fixture::commit(&git, None, &[], "base");
+        let a1 = fixture::commit(&git, None, &[base], "a1");
+        let b1 = fixture::commit(&git, None, &[base], "b1");
+        let merge = fixture::commit(&git, None, &[a1, b1], "merge");
+        let local = fixture::commit(&git, None, &[merge], "a2");
+        let remote = fixture::commit(&git, None, &[b1], "b2");
+
+        // The merge base is b1, so a1, the merge and a2 are ahead and only b2 is behind
+        assert_eq!(ahead_behind(&git, local, remote), [3, 1, 3, 1]);
+    }
+
+    #[test]
+    fn test_lanes() {
+        // d (merge of c and b) -> c -> a, b -> a
+        let mut lanes = Lanes::default();
+
+        assert_eq!(lanes.next(&'d', &['c', 'b']), vec!["*", "|\\"]);
+        assert_eq!(lanes.padding(), "| |");
+        assert_eq!(lanes.next(&'c', &['a']), vec!["* |"]);
+        assert_eq!(lanes.next(&'b', &['a']), vec!["| *", "|/"]);
+        assert_eq!(lanes.padding(), "|");
+        assert_eq!(lanes.next(&'a', &[]), vec!["*"]);
+        assert_eq!(lanes.padding(), "");
+    }
 }
@@ -6,12 +6,13 @@ use std::path::{Path, PathBuf};
 use clap::{CommandFactory, Parser, ValueHint};
 use clap_complete::{generate, Shell};
 use colored::Colorize;
-use git::Repo;
+use git::{Optional, Repo};
 use git2::{Repository, RepositoryOpenFlags};
 use resolve_path::PathResolveExt;
 use tracing_subscriber::EnvFilter;
 
 mod cmd;
+mod error;
 mod git;
 mod graph;
 mod progress;
@@ -31,6 +32,29 @@ struct Opts {
 
     #[arg(long = "generate", value_enum)]
     generator: Option<Shell>,
+
+    #[clap(
+        long,
+        global = true,
+        help = "Stable, color-free and tab-delimited output for scripts"
+    )]
+    plumbing: bool,
+
+    #[clap(
+        short = 'z',
+        global = true,
+        help = "NUL-terminate plumbing and porcelain records, paths are left unquoted"
+    )]
+    null_terminated: bool,
+
+    #[clap(
+        long,
+        global = true,
+        value_enum,
+        default_value = "auto",
+        help = "When to use colors"
+    )]
+    color: term::ColorChoice,
 }
 
 #[derive(Parser)]
@@ -42,8 +66,10 @@ enum Cmd {
     Chore(cmd::commit::Opts),
     Clone(cmd::clone::Opts),
     Commit(cmd::commit::Opts),
+    CommitGraph(cmd::commit_graph::Opts),
     Amend(cmd::amend::Opts),
     Push(cmd::push::Opts),
+    Rebase(cmd::rebase::Opts),
     Fetch(cmd::fetch::Opts),
     Pull(cmd::pull::Opts),
     Sync(cmd::sync::Opts),
@@ -51,8 +77,18 @@ enum Cmd {
     Diff(cmd::diff::Opts),
     Stash(cmd::stash::Opts),
     Unstash(cmd::unstash::Opts),
+    Unstage(cmd::unstage::Opts),
+    Resolve(cmd::resolve::Opts),
+    Mergetool(cmd::mergetool::Opts),
+    Maintenance(cmd::maintenance::Opts),
+    Reflog(cmd::reflog::Opts),
+    Prune(cmd::prune::Opts),
+    Config(cmd::config::Opts),
+    Dash(cmd::dash::Opts),
     Branch(cmd::branch::Opts),
     Checkout(cmd::checkout::Opts),
+    Worktree(cmd::worktree::Opts),
+    Submodule(cmd::submodule::Opts),
 }
 
 fn open_gix(path: impl AsRef<Path>) -> Result<gix::Repository, gix::open::Error> {
@@ -86,6 +122,63 @@ fn open_repo(path: impl AsRef<Path>) -> Result<Repo, Box<dyn Error>> {
     )?))
 }
 
+fn run(dir: &Path, repo: Repo, cmd: Cmd) -> Result<(), Box<dyn Error>> {
+    match cmd {
+        Cmd::Add(opts) => cmd::add::run(repo, opts),
+        Cmd::Fix(opts) => cmd::commit::with_prefix("fix", repo, opts),
+        Cmd::Feat(opts) => cmd::commit::with_prefix("feat", repo, opts),
+        Cmd::Chore(opts) => cmd::commit::with_prefix("chore", repo, opts),
+        Cmd::Refactor(opts) => cmd::commit::with_prefix("refactor", repo, opts),
+        Cmd::Commit(opts) => cmd::commit::run(repo, opts),
+        Cmd::CommitGraph(opts) => cmd::commit_graph::run(repo, opts),
+        Cmd::Amend(opts) => cmd::amend::run(repo, opts),
+        Cmd::Push(opts) => cmd::push::run(repo, opts),
+        Cmd::Rebase(opts) => cmd::rebase::run(repo, opts),
+        Cmd::Fetch(opts) => cmd::fetch::run(repo, opts),
+        Cmd::Pull(opts) => cmd::pull::run(repo, opts),
+        Cmd::Sync(opts) => cmd::sync::run(repo, opts),
+        Cmd::List(opts) => cmd::list::run(repo, opts),
+        Cmd::Diff(opts) => cmd::diff::run(repo, opts),
+        Cmd::Stash(opts) => cmd::stash::run(repo, opts),
+        Cmd::Unstash(opts) => cmd::unstash::run(repo, opts),
+        Cmd::Unstage(opts) => cmd::unstage::run(repo, opts),
+        Cmd::Resolve(opts) => cmd::resolve::run(repo, opts),
+        Cmd::Mergetool(opts) => cmd::mergetool::run(repo, opts),
+        Cmd::Maintenance(opts) => cmd::maintenance::run(repo, opts),
+        Cmd::Reflog(opts) => cmd::reflog::run(repo, opts),
+        Cmd::Prune(opts) => cmd::prune::run(repo, opts),
+        Cmd::Config(opts) => cmd::config::run(repo, opts),
+        Cmd::Dash(opts) => cmd::dash::run(repo, opts),
+        Cmd::Branch(opts) => cmd::branch::run(repo, opts),
+        Cmd::Checkout(opts) => cmd::checkout::run(repo, opts),
+        Cmd::Worktree(opts) => cmd::worktree::run(repo, opts),
+        Cmd::Submodule(opts) => cmd::submodule::run(repo, opts),
+        Cmd::Clone(opts) => cmd::clone::run(dir, opts),
+    }
+}
+
+/// Runs `src.defaultCommand` (e.g. `list commit --short`), or status when it's not configured.
+fn run_default(dir: &Path) -> Result<(), Box<dyn Error>> {
+    let repo = open_repo(dir)?;
+    let command = repo.config()?.get_string("src.defaultCommand").optional()?;
+
+    match command.as_deref().map(str::trim) {
+        None | Some("status") => cmd::status::run(open_gix(dir)?, cmd::status::Opts::default()),
+        Some(command) => {
+            // Quoted arguments are kept together, like `list commit --grep "fix bug"`
+            let args = shell_words::split(command)
+                .map_err(|e| format!("invalid src.defaultCommand: {e}"))?;
+            let Some(cmd) =
+                Opts::try_parse_from(std::iter::once("src".to_string()).chain(args))?.cmd
+            else {
+                return Err(format!("invalid src.defaultCommand: {command}").into());
+            };
+
+            run(dir, repo, cmd)
+        }
+    }
+}
+
 fn main() {
     tracing_subscriber::fmt()
         .with_env_filter(EnvFilter::from_default_env())
@@ -93,6 +186,13 @@ fn main() {
 
     let opts = Opts::parse();
 
+    term::set_plumbing(opts.plumbing);
+    term::set_null_terminated(opts.null_terminated);
+    term::set_color(opts.color);
+
+    // Without the handler Ctrl-C still works, it just can't restore the terminal
+    let _ = term::setup_interrupt();
+
     if let Some(generator) = opts.generator {
         let mut cmd = Opts::command();
         let bin_name = cmd.get_name().to_string();
@@ -101,43 +201,30 @@ fn main() {
     }
 
     let app = || match opts.cmd {
-        Some(Cmd::Clone(opts)) => cmd::clone::run(opts),
-        cmd => match cmd {
-            Some(cmd) => {
-                let repo = open_repo(&opts.dir)?;
-
-                match cmd {
-                    Cmd::Add(opts) => cmd::add::run(repo, opts),
-                    Cmd::Fix(opts) => cmd::commit::with_prefix("fix", repo, opts),
-                    Cmd::Feat(opts) => cmd::commit::with_prefix("feat", repo, opts),
-                    Cmd::Chore(opts) => cmd::commit::with_prefix("chore", repo, opts),
-                    Cmd::Refactor(opts) => cmd::commit::with_prefix("refactor", repo, opts),
-                    Cmd::Commit(opts) => cmd::commit::run(repo, opts),
-                    Cmd::Amend(opts) => cmd::amend::run(repo, opts),
-                    Cmd::Push(opts) => cmd::push::run(repo, opts),
-                    Cmd::Fetch(opts) => cmd::fetch::run(repo, opts),
-                    Cmd::Pull(opts) => cmd::pull::run(repo, opts),
-                    Cmd::Sync(opts) => cmd::sync::run(repo, opts),
-                    Cmd::List(opts) => cmd::list::run(repo, opts),
-                    Cmd::Diff(opts) => cmd::diff::run(repo, opts),
-                    Cmd::Stash(opts) => cmd::stash::run(repo, opts),
-                    Cmd::Unstash(opts) => cmd::unstash::run(repo, opts),
-                    Cmd::Branch(opts) => cmd::branch::run(repo, opts),
-                    Cmd::Checkout(opts) => cmd::checkout::run(repo, opts),
-                    Cmd::Clone(_) => unreachable!(),
-                }
-            }
-            None => match opts.branch {
-                Some(branch) => cmd::checkout::run(
-                    open_repo(&opts.dir)?,
-                    cmd::checkout::Opts::with_branch(branch),
-                ),
-                None => cmd::status::run(open_gix(opts.dir)?, cmd::status::Opts::default()),
-            },
+        Some(Cmd::Clone(clone)) => cmd::clone::run(&opts.dir, clone),
+        Some(cmd) => run(&opts.dir, open_repo(&opts.dir)?, cmd),
+        None => match opts.branch {
+            Some(branch) => cmd::checkout::run(
+                open_repo(&opts.dir)?,
+                cmd::checkout::Opts::with_branch(branch),
+            ),
+            None => run_default(&opts.dir),
         },
     };
 
     if let Err(e) = app() {
+        if term::interrupted() {
+            eprintln!("{}", "⚠️ interrupted".red());
+            std::process::exit(130);
+        }
+
+        let e = error::AppError::from(e);
         eprintln!("{}", format!("⚠️ {e}").red());
+
+        if let Some(hint) = e.hint() {
+            eprintln!("{}", format!("hint: {hint}").dimmed());
+        }
+
+        std::process::exit(e.exit_code());
     }
 }
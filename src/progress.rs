@@ -15,8 +15,13 @@ pub fn tree() -> Arc<Root> {
     )
 }
 
-pub fn setup_line_renderer(progress: &Arc<Root>) -> JoinHandle {
-    prodash::render::line(
+/// Draws the progress on stderr, nothing is drawn in plumbing mode.
+pub fn setup_line_renderer(progress: &Arc<Root>) -> Option<JoinHandle> {
+    if crate::term::plumbing() {
+        return None;
+    }
+
+    Some(prodash::render::line(
         std::io::stderr(),
         std::sync::Arc::downgrade(progress),
         prodash::render::line::Options {
@@ -29,5 +34,5 @@ pub fn setup_line_renderer(progress: &Arc<Root>) -> JoinHandle {
             ..prodash::render::line::Options::default()
         }
         .auto_configure(prodash::render::line::StreamKind::Stderr),
-    )
+    ))
 }
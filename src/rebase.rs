@@ -1,7 +1,10 @@
-use std::{fs, path::Path, str::FromStr};
+use std::{fmt, fs, path::Path, str::FromStr};
 
 use gix::Repository;
 
+/// Directory in `.git` holding the state of a rebase started by src.
+pub const REBASE_DIR: &str = "src-rebase";
+
 #[derive(Debug, thiserror::Error)]
 pub enum RebaseError {
     #[error("I/O error: {0}")]
@@ -19,6 +22,21 @@ pub enum RebaseOperationType {
     Squash,
     Fixup,
     Exec,
+    Drop,
+}
+
+impl RebaseOperationType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            RebaseOperationType::Pick => "pick",
+            RebaseOperationType::Reword => "reword",
+            RebaseOperationType::Edit => "edit",
+            RebaseOperationType::Squash => "squash",
+            RebaseOperationType::Fixup => "fixup",
+            RebaseOperationType::Exec => "exec",
+            RebaseOperationType::Drop => "drop",
+        }
+    }
 }
 
 pub struct RebaseOp {
@@ -47,6 +65,7 @@ impl FromStr for RebaseOp {
             "s" | "squash" => RebaseOperationType::Squash,
             "f" | "fixup" => RebaseOperationType::Fixup,
             "x" | "exec" => RebaseOperationType::Exec,
+            "d" | "drop" => RebaseOperationType::Drop,
             _ => {
                 return Err(RebaseError::Parse(
                     "invalid rebase operation type".to_string(),
@@ -62,6 +81,12 @@ impl FromStr for RebaseOp {
     }
 }
 
+impl fmt::Display for RebaseOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", self.ty.name(), self.oid, self.message)
+    }
+}
+
 pub struct Rebase {
     pub operations: Vec<RebaseOp>,
 }
@@ -80,4 +105,32 @@ impl Rebase {
     pub fn from_repo(repo: &Repository) -> Result<Self, RebaseError> {
         Rebase::from_path(&repo.path().join("rebase-merge/git-rebase-todo.backup"))
     }
+
+    /// Reads the remaining operations of a rebase started by src.
+    pub fn from_src(repo: &Repository) -> Result<Self, RebaseError> {
+        Rebase::from_path(&repo.path().join(REBASE_DIR).join("todo"))
+    }
+}
+
+pub struct ApplyProgress {
+    pub next: usize,
+    pub last: usize,
+}
+
+fn read_number(path: &Path) -> Result<usize, RebaseError> {
+    fs::read_to_string(path)?
+        .trim()
+        .parse()
+        .map_err(|e| RebaseError::Parse(format!("{}: {e}", path.display())))
+}
+
+impl ApplyProgress {
+    pub fn from_repo(repo: &Repository) -> Result<Self, RebaseError> {
+        let dir = repo.path().join("rebase-apply");
+
+        Ok(Self {
+            next: read_number(&dir.join("next"))?,
+            last: read_number(&dir.join("last"))?,
+        })
+    }
 }
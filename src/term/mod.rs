@@ -1,19 +1,126 @@
 use std::{
-    sync::mpsc::Receiver,
+    env,
+    error::Error,
+    fs,
+    io::{self, IsTerminal, Write},
+    process,
+    process::Command,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc::Receiver,
+    },
     thread::{self, JoinHandle},
-    time::Instant,
+    time::{Duration, Instant},
 };
 
-use inquire::{error::InquireResult, ui::RenderConfig, Confirm};
+use inquire::{error::InquireResult, ui::RenderConfig, Confirm, Select};
 use progress::ProgressBar;
 
 use crate::git::{ProgressEvent, SidebandOp};
 
 pub mod node;
+pub mod pager;
 pub mod progress;
 pub mod render;
 pub mod select;
 
+static PLUMBING: AtomicBool = AtomicBool::new(false);
+static NULL_TERMINATED: AtomicBool = AtomicBool::new(false);
+
+/// Number of running operations that stop on their own when interrupted.
+static COOPERATING: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Clone, Copy, Default, clap::ValueEnum)]
+pub enum ColorChoice {
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+/// Enables plumbing mode: stable, color-free output without pagers or progress bars.
+pub fn set_plumbing(plumbing: bool) {
+    PLUMBING.store(plumbing, Ordering::Relaxed);
+}
+
+/// Applies the color policy for all output, `auto` disables colors when `NO_COLOR` is set or
+/// stdout isn't a terminal. Colors are always disabled in plumbing mode.
+pub fn set_color(choice: ColorChoice) {
+    let enabled = match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => {
+            env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+                && io::stdout().is_terminal()
+        }
+    };
+
+    colored::control::set_override(enabled && !plumbing());
+}
+
+pub fn plumbing() -> bool {
+    PLUMBING.load(Ordering::Relaxed)
+}
+
+/// Terminates plumbing records with NUL instead of a newline, text is written unquoted.
+pub fn set_null_terminated(null_terminated: bool) {
+    NULL_TERMINATED.store(null_terminated, Ordering::Relaxed);
+}
+
+pub fn null_terminated() -> bool {
+    NULL_TERMINATED.load(Ordering::Relaxed)
+}
+
+pub struct Cooperate(());
+
+/// Marks an operation that checks [`interrupted`] and winds down by itself (clearing its progress
+/// bars) until the guard is dropped, Ctrl-C exits right away while none is running.
+pub fn cooperate() -> Cooperate {
+    COOPERATING.fetch_add(1, Ordering::SeqCst);
+    Cooperate(())
+}
+
+impl Drop for Cooperate {
+    fn drop(&mut self) {
+        COOPERATING.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+pub fn interrupted() -> bool {
+    gix::interrupt::IS_INTERRUPTED.load(Ordering::Relaxed)
+}
+
+fn restore_terminal() {
+    let _ = crossterm::terminal::disable_raw_mode();
+    let _ = crossterm::execute!(io::stderr(), crossterm::cursor::Show);
+    eprintln!();
+}
+
+/// Installs the Ctrl-C handler. The handler itself only raises a flag, a watcher thread restores
+/// the terminal and exits unless a cooperating operation is running.
+pub fn setup_interrupt() -> io::Result<()> {
+    // SAFETY: the handler doesn't do anything besides setting `IS_INTERRUPTED`
+    unsafe { gix::interrupt::init_handler(1, || {}) }?;
+
+    thread::spawn(|| loop {
+        thread::sleep(Duration::from_millis(50));
+
+        if interrupted() && COOPERATING.load(Ordering::SeqCst) == 0 {
+            restore_terminal();
+            process::exit(130);
+        }
+    });
+
+    Ok(())
+}
+
+/// Width of the terminal, falls back to 80 columns if it can't be determined.
+pub fn width() -> usize {
+    crossterm::terminal::size()
+        .map(|(width, _)| width as usize)
+        .unwrap_or(80)
+}
+
 pub fn confirm(prompt: &str) -> InquireResult<bool> {
     let mut config = RenderConfig::default_colored();
     config.prompt.fg = Some(inquire::ui::Color::LightCyan);
@@ -24,21 +131,184 @@ pub fn confirm(prompt: &str) -> InquireResult<bool> {
         .prompt()
 }
 
+pub fn choose<'a>(prompt: &str, options: Vec<&'a str>) -> InquireResult<&'a str> {
+    let mut config = RenderConfig::default_colored();
+    config.prompt.fg = Some(inquire::ui::Color::LightCyan);
+
+    Select::new(prompt, options)
+        .with_render_config(config)
+        .prompt()
+}
+
+/// Opens the user's editor (`GIT_EDITOR`, `core.editor`, `VISUAL`, `EDITOR` or vi) to edit the
+/// given content, returning the result without comment lines.
+pub fn edit(editor: Option<&str>, content: &str) -> Result<String, Box<dyn Error>> {
+    let editor = env::var("GIT_EDITOR")
+        .ok()
+        .or_else(|| editor.map(ToString::to_string))
+        .or_else(|| env::var("VISUAL").ok())
+        .or_else(|| env::var("EDITOR").ok())
+        .unwrap_or_else(|| "vi".to_string());
+
+    let mut file = tempfile::Builder::new()
+        .prefix("COMMIT_EDITMSG")
+        .tempfile()?;
+    file.write_all(content.as_bytes())?;
+    file.flush()?;
+
+    // Run through the shell as the editor may contain arguments (e.g. `code --wait`)
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(format!("{editor} \"$@\""))
+        .arg(&editor)
+        .arg(file.path())
+        .status()?;
+
+    if !status.success() {
+        return Err(format!("editor exited with {status}").into());
+    }
+
+    Ok(fs::read_to_string(file.path())?
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string())
+}
+
+#[derive(Clone, Copy, Default, clap::ValueEnum)]
+pub enum ProgressFormat {
+    #[default]
+    Bar,
+    Json,
+}
+
+/// Renders progress events as progress bars or as NDJSON lines on stderr (for scripts and editor
+/// integrations).
+pub fn setup_progress(rx: Receiver<ProgressEvent>, format: ProgressFormat) -> JoinHandle<()> {
+    match format {
+        ProgressFormat::Bar if plumbing() => thread::spawn(move || rx.into_iter().for_each(drop)),
+        ProgressFormat::Bar => setup_progress_bar(rx),
+        ProgressFormat::Json => setup_progress_json(rx),
+    }
+}
+
+fn setup_progress_json(rx: Receiver<ProgressEvent>) -> JoinHandle<()> {
+    thread::spawn(move || {
+        for event in rx {
+            let (stage, bytes, current, total) = match event {
+                ProgressEvent::Transfer(bytes, current, total)
+                | ProgressEvent::PushTransfer(bytes, current, total) => {
+                    ("transfer", Some(bytes), current, total)
+                }
+                ProgressEvent::Packing(current, total) => ("packing", None, current, total),
+                ProgressEvent::Sideband(op, current, total) => (
+                    match op {
+                        SidebandOp::Counting => "counting",
+                        SidebandOp::Compressing => "compressing",
+                        SidebandOp::Resolving => "resolving",
+                    },
+                    None,
+                    current,
+                    total,
+                ),
+            };
+
+            match bytes {
+                Some(bytes) => eprintln!(
+                    "{{\"stage\":\"{stage}\",\"current\":{current},\"total\":{total},\"bytes\":{bytes}}}"
+                ),
+                None => eprintln!(
+                    "{{\"stage\":\"{stage}\",\"current\":{current},\"total\":{total}}}"
+                ),
+            }
+        }
+    })
+}
+
+/// Formats a number of bytes in binary units (e.g. `1.2 MiB`).
+fn format_bytes(bytes: f64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+
+    if bytes < 1024.0 {
+        return format!("{bytes:.0} B");
+    }
+
+    let mut value = bytes / 1024.0;
+    let mut unit = UNITS[0];
+
+    for next in &UNITS[1..] {
+        if value < 1024.0 {
+            break;
+        }
+
+        value /= 1024.0;
+        unit = next;
+    }
+
+    format!("{value:.1} {unit}")
+}
+
+/// Tracks the transferred bytes over time to compute the throughput.
+#[derive(Default)]
+struct Throughput {
+    sample: Option<(Instant, usize)>,
+    rate: Option<f64>,
+}
+
+impl Throughput {
+    fn update(&mut self, bytes: usize) -> Option<f64> {
+        let now = Instant::now();
+
+        match self.sample {
+            Some((since, last)) => {
+                let elapsed = now.duration_since(since).as_secs_f64();
+
+                // Measure over at least half a second to avoid a jumpy readout
+                if elapsed >= 0.5 {
+                    let rate = bytes.saturating_sub(last) as f64 / elapsed;
+
+                    self.rate = Some(match self.rate {
+                        Some(previous) => previous * 0.5 + rate * 0.5,
+                        None => rate,
+                    });
+                    self.sample = Some((now, bytes));
+                }
+            }
+            None => self.sample = Some((now, bytes)),
+        }
+
+        self.rate
+    }
+}
+
 pub fn setup_progress_bar(rx: Receiver<ProgressEvent>) -> JoinHandle<()> {
     thread::spawn(move || {
         let mut now = Instant::now();
         let mut bar = ProgressBar::with_multiple(vec!["Remote", "Transfer", "Packing"]);
+        let mut throughput = Throughput::default();
 
         bar.draw();
 
         for event in rx {
             match event {
-                ProgressEvent::Transfer(current, total) => {
-                    bar.set_message(1, format!("{current}/{total} objects"));
+                ProgressEvent::Transfer(bytes, current, total) => {
+                    let rate = throughput
+                        .update(bytes)
+                        .map(|rate| format!(", {}/s", format_bytes(rate)))
+                        .unwrap_or_default();
+
+                    bar.set_message(1, format!("{current}/{total} objects{rate}"));
                     bar.set_progress(1, current, total);
                 }
                 ProgressEvent::PushTransfer(bytes, current, total) => {
-                    bar.set_message(1, format!("{bytes} bytes"));
+                    let rate = throughput
+                        .update(bytes)
+                        .map(|rate| format!(", {}/s", format_bytes(rate)))
+                        .unwrap_or_default();
+
+                    bar.set_message(1, format!("{}{rate}", format_bytes(bytes as f64)));
                     bar.set_progress(1, current, total);
                 }
                 ProgressEvent::Packing(current, total) => bar.set_progress(2, current, total),
@@ -73,7 +73,7 @@ pub(crate) use text;
 
 pub mod prelude {
     pub(crate) use super::{
-        block, breadcrumb, continued, dimmed, icon, label, multi_line, spacer, text,
+        block, breadcrumb, column, continued, dimmed, icon, label, multi_line, spacer, text,
     };
     pub use super::{message_with_icon, Attribute, Icon, Indicator, Node, Status};
 }
@@ -130,6 +130,7 @@ pub enum Indicator {
     Modified,
     Renamed,
     Deleted,
+    Warning,
 }
 
 #[derive(Debug)]
@@ -144,6 +145,8 @@ pub enum Node {
     Continued(Box<Node>),
     Breadcrumb(Vec<Node>),
     Text(Cow<'static, str>),
+    /// Text capped to the remaining width of the line (see `TermRenderer::with_width`).
+    Capped(Cow<'static, str>),
     Attribute(Attribute),
     Status(Status, Box<Node>),
     Column(Box<Node>, Box<Node>),
@@ -166,16 +169,6 @@ impl Node {
         )
     }
 
-    pub fn text_capped(text: impl ToString, cap: usize) -> Node {
-        let text = text.to_string();
-
-        if text.len() > cap {
-            Node::Text(format!("{}...", &text[..cap - 3]).into())
-        } else {
-            Node::Text(text.into())
-        }
-    }
-
     pub fn with_status(self, status: Status) -> Self {
         Node::Status(status, Box::new(self))
     }
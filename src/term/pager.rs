@@ -0,0 +1,123 @@
+use std::{
+    env,
+    error::Error,
+    fmt,
+    io::{self, IsTerminal, Write},
+    process::{Child, Command, Stdio},
+    sync::atomic::Ordering,
+    thread,
+};
+
+use super::Cooperate;
+
+/// Resolves the pager like git does: `GIT_PAGER`, `core.pager` and then `PAGER`.
+pub fn configured(core_pager: Option<String>) -> Option<String> {
+    env::var("GIT_PAGER")
+        .ok()
+        .or(core_pager)
+        .or_else(|| env::var("PAGER").ok())
+}
+
+pub enum Pager {
+    Builtin(minus::Pager),
+    /// The guard keeps Ctrl-C from exiting while the pager runs, like git which ignores SIGINT
+    /// until the pager exits. The pager (e.g. `less` while searching) handles it instead.
+    External(Child, Cooperate),
+    Stdout,
+}
+
+impl Pager {
+    /// Uses the given pager command or the built-in pager if it's not set. Like git, an empty
+    /// command or `cat` disables paging, as does output that isn't a terminal.
+    pub fn new(command: Option<&str>, prompt: impl Into<String>) -> Result<Self, Box<dyn Error>> {
+        match command.map(str::trim) {
+            None => {
+                let pager = minus::Pager::new();
+                pager.set_prompt(prompt)?;
+                Ok(Self::Builtin(pager))
+            }
+            Some("" | "cat") => Ok(Self::Stdout),
+            Some(_) if !io::stdout().is_terminal() => Ok(Self::Stdout),
+            Some(command) => {
+                let mut cmd = Command::new("sh");
+                cmd.arg("-c").arg(command).stdin(Stdio::piped());
+
+                // Same defaults as git: quit if the output fits on one screen and keep colors
+                if env::var_os("LESS").is_none() {
+                    cmd.env("LESS", "FRX");
+                }
+
+                if env::var_os("LV").is_none() {
+                    cmd.env("LV", "-c");
+                }
+
+                Ok(Self::External(cmd.spawn()?, super::cooperate()))
+            }
+        }
+    }
+
+    /// Writes all output before showing it.
+    pub fn page_all(
+        mut self,
+        f: impl FnOnce(&mut Self) -> Result<(), Box<dyn Error>>,
+    ) -> Result<(), Box<dyn Error>> {
+        f(&mut self)?;
+        self.finish()
+    }
+
+    /// Shows the output while it's being written, useful for slow output like patches.
+    pub fn page_dynamic(
+        mut self,
+        f: impl FnOnce(&mut Self) -> Result<(), Box<dyn Error>>,
+    ) -> Result<(), Box<dyn Error>> {
+        let Self::Builtin(pager) = &self else {
+            return self.page_all(f);
+        };
+
+        let handle = {
+            let pager = pager.clone();
+            thread::spawn(move || minus::dynamic_paging(pager))
+        };
+
+        f(&mut self)?;
+        handle.join().map_err(|_| "pager panicked")??;
+
+        Ok(())
+    }
+
+    fn finish(self) -> Result<(), Box<dyn Error>> {
+        match self {
+            Self::Builtin(pager) => minus::page_all(pager)?,
+            Self::External(mut child, _cooperate) => {
+                // Closing stdin signals the end of the output
+                drop(child.stdin.take());
+                child.wait()?;
+
+                // Ctrl-C was meant for the pager, don't exit once the guard is dropped
+                gix::interrupt::IS_INTERRUPTED.store(false, Ordering::SeqCst);
+            }
+            Self::Stdout => io::stdout().flush()?,
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Write for Pager {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let result = match self {
+            Self::Builtin(pager) => return pager.write_str(s),
+            Self::External(child, _) => match child.stdin.as_mut() {
+                Some(stdin) => stdin.write_all(s.as_bytes()),
+                None => return Err(fmt::Error),
+            },
+            Self::Stdout => io::stdout().write_all(s.as_bytes()),
+        };
+
+        match result {
+            // The user quit the pager before all output was written
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+            result => result.map_err(|_| fmt::Error),
+        }
+    }
+}
@@ -1,6 +1,8 @@
 use std::{
+    collections::VecDeque,
     io::{stdout, Write},
     sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant},
 };
 
 use colored::Colorize;
@@ -11,6 +13,10 @@ const MOVE_DOWN: &str = "\x1B[1B";
 const MOVE_RIGHT: &str = "\x1b[1C";
 const DELETE_CHAR: &str = "\x08";
 const CLEAR_LINE: &str = "\x1B[2K";
+const CLEAR_BELOW: &str = "\x1B[0J";
+
+/// Number of (instant, current) samples used to smooth the transfer rate.
+const SAMPLES: usize = 10;
 
 fn decode_chars(s: &str) -> Vec<char> {
     String::from_utf8_lossy(&strip_ansi_escapes::strip(s.as_bytes()))
@@ -18,6 +24,11 @@ fn decode_chars(s: &str) -> Vec<char> {
         .collect()
 }
 
+/// Lines wider than the terminal wrap, so a line can take up more than one row.
+fn rows(line: &str, columns: usize) -> usize {
+    decode_chars(line).len().max(1).div_ceil(columns.max(1))
+}
+
 fn move_begin() {
     print!("{MOVE_BEGIN}");
 }
@@ -48,11 +59,25 @@ fn erase_to_end() {
     print!("\x1B[0K");
 }
 
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+
+    match secs {
+        0..=59 => format!("{secs}s"),
+        60..=3599 => format!("{}m{:02}s", secs / 60, secs % 60),
+        _ => format!("{}h{:02}m", secs / 3600, secs % 3600 / 60),
+    }
+}
+
 struct Bar {
     name: String,
     current: AtomicUsize,
     total: AtomicUsize,
     message: Option<String>,
+    started: Option<Instant>,
+    samples: VecDeque<(Instant, usize)>,
+    dirty: bool,
+    drawn_secs: u64,
 }
 
 impl Bar {
@@ -62,6 +87,76 @@ impl Bar {
             message: None,
             current: AtomicUsize::new(0),
             total: AtomicUsize::new(100),
+            started: None,
+            samples: VecDeque::with_capacity(SAMPLES),
+            dirty: true,
+            drawn_secs: 0,
+        }
+    }
+
+    /// Whether the bar changed since it was last drawn, the elapsed time counts once a second.
+    fn is_dirty(&self) -> bool {
+        self.dirty
+            || self
+                .started
+                .is_some_and(|started| started.elapsed().as_secs() != self.drawn_secs)
+    }
+
+    fn drawn(&mut self) {
+        self.dirty = false;
+        self.drawn_secs = self
+            .started
+            .map(|started| started.elapsed().as_secs())
+            .unwrap_or_default();
+    }
+
+    fn sample(&mut self, now: Instant) {
+        let current = self.current.load(Ordering::Relaxed);
+
+        if current == 0
+            || self
+                .samples
+                .back()
+                .is_some_and(|&(_, last)| last == current)
+        {
+            return;
+        }
+
+        self.started.get_or_insert(now);
+
+        if self.samples.len() == SAMPLES {
+            self.samples.pop_front();
+        }
+
+        self.samples.push_back((now, current));
+    }
+
+    /// Estimates the time remaining from the rate over the recent samples.
+    fn eta(&self, current: usize, total: usize) -> Option<Duration> {
+        let (&(first, from), &(last, to)) = (self.samples.front()?, self.samples.back()?);
+        let elapsed = last.duration_since(first).as_secs_f64();
+
+        if total == 0 || to <= from || elapsed <= 0.0 {
+            return None;
+        }
+
+        let rate = (to - from) as f64 / elapsed;
+
+        Some(Duration::from_secs_f64(
+            total.saturating_sub(current) as f64 / rate,
+        ))
+    }
+
+    fn timing(&self, current: usize, total: usize) -> String {
+        let Some(started) = self.started else {
+            return String::new();
+        };
+
+        let elapsed = format_duration(started.elapsed());
+
+        match self.eta(current, total) {
+            Some(eta) => format!(" {elapsed} / {} left", format_duration(eta)),
+            None => format!(" {elapsed}"),
         }
     }
 
@@ -81,7 +176,7 @@ impl Bar {
         let left = (width as f64 * (current as f64 / total as f64)).floor() as usize;
 
         format!(
-            "{}{} {}{}{}{}{} {}",
+            "{}{} {}{}{}{}{} {}{}",
             self.name,
             " ".repeat(prefix - self.name.len()),
             "[".blue().bold(),
@@ -94,13 +189,15 @@ impl Bar {
             if left > 0 { "❯" } else { "" }.bold(),
             if left > 0 { " " } else { "-" }.repeat(width - left),
             "]".blue().bold(),
-            self.message.as_deref().unwrap_or_default()
+            self.message.as_deref().unwrap_or_default(),
+            self.timing(current, total).dimmed()
         )
     }
 }
 
 pub struct ProgressBar {
     width: usize,
+    columns: usize,
     bars: Vec<Bar>,
     previous_state: Option<Vec<String>>,
 }
@@ -110,20 +207,33 @@ impl ProgressBar {
         Self {
             previous_state: None,
             width: 24,
+            columns: super::width(),
             bars: names.into_iter().map(Bar::new).collect(),
         }
     }
 
     pub fn set_message(&mut self, idx: usize, message: impl ToString) {
-        if idx < self.bars.len() {
-            self.bars[idx].message = Some(message.to_string());
+        let Some(bar) = self.bars.get_mut(idx) else {
+            return;
+        };
+
+        let message = message.to_string();
+
+        if bar.message.as_ref() != Some(&message) {
+            bar.message = Some(message);
+            bar.dirty = true;
         }
     }
 
     pub fn set_progress(&mut self, idx: usize, current: usize, total: usize) {
-        if idx < self.bars.len() {
-            self.bars[idx].current.store(current, Ordering::Relaxed);
-            self.bars[idx].total.store(total, Ordering::Relaxed);
+        // Updates between draws are coalesced, only the last one is drawn
+        if let Some(bar) = self.bars.get_mut(idx) {
+            let previous = (
+                bar.current.swap(current, Ordering::Relaxed),
+                bar.total.swap(total, Ordering::Relaxed),
+            );
+
+            bar.dirty |= previous != (current, total);
         }
     }
 
@@ -141,7 +251,33 @@ impl ProgressBar {
         }
     }
 
+    /// Erases the bars after the terminal was resized, the cursor math of the previous draw
+    /// doesn't hold anymore when lines (un)wrapped.
+    fn reset(&mut self, columns: usize) {
+        if let Some(state) = self.previous_state.take() {
+            move_begin();
+            move_up(state.iter().map(|line| rows(line, columns)).sum());
+            print!("{CLEAR_BELOW}");
+        }
+
+        for bar in self.bars.iter_mut() {
+            bar.dirty = true;
+        }
+    }
+
     pub fn draw(&mut self) {
+        let now = Instant::now();
+        let columns = super::width();
+
+        if columns != self.columns {
+            self.columns = columns;
+            self.reset(columns);
+        }
+
+        for bar in self.bars.iter_mut() {
+            bar.sample(now);
+        }
+
         let prefix = self
             .bars
             .iter()
@@ -149,40 +285,42 @@ impl ProgressBar {
             .max()
             .unwrap_or(0);
 
-        let mut new_state = vec![];
+        let new_state = match self.previous_state.take() {
+            Some(state) if !self.bars.iter().any(Bar::is_dirty) => state,
+            Some(mut state) => {
+                for (i, bar) in self.bars.iter_mut().enumerate().rev() {
+                    move_up(1);
 
-        match self.previous_state.as_ref() {
-            Some(state) => {
-                for (i, bar) in self.bars.iter().rev().enumerate() {
-                    let current_line = &state[i];
-                    let new_line = bar.render(prefix, self.width);
+                    if !bar.is_dirty() {
+                        continue;
+                    }
 
-                    move_up(1);
+                    let new_line = bar.render(prefix, self.width);
+                    bar.drawn();
 
-                    if current_line == &new_line {
-                        new_state.push(new_line);
+                    if state[i] == new_line {
                         continue;
                     }
 
                     let new = decode_chars(&new_line);
-                    let current = decode_chars(current_line);
+                    let current = decode_chars(&state[i]);
 
                     if new.len() < current.len() {
                         move_begin();
                         print!("{new_line}");
                         erase_to_end();
                     } else {
-                        for (i, c) in new.iter().copied().enumerate() {
-                            let current_char = current.get(i).copied().unwrap_or(' ');
+                        for (pos, c) in new.iter().copied().enumerate() {
+                            let current_char = current.get(pos).copied().unwrap_or(' ');
 
                             if current_char == c {
                                 continue;
                             }
 
                             move_begin();
-                            move_right(i + 1);
+                            move_right(pos + 1);
 
-                            if i <= current.len() {
+                            if pos <= current.len() {
                                 delete_char();
                             }
 
@@ -190,22 +328,26 @@ impl ProgressBar {
                         }
                     }
 
-                    new_state.push(new_line);
+                    state[i] = new_line;
                 }
+
+                move_down(self.bars.len());
+                move_begin();
+                state
             }
             None => {
-                for bar in self.bars.iter() {
+                let mut state = vec![];
+
+                for bar in self.bars.iter_mut() {
                     let new_line = bar.render(prefix, self.width);
+                    bar.drawn();
                     println!("{new_line}");
-                    new_state.push(new_line);
+                    state.push(new_line);
                 }
-            }
-        }
 
-        if self.previous_state.is_some() {
-            move_down(self.bars.len());
-            move_begin();
-        }
+                state
+            }
+        };
 
         // Try to flush the output buffer
         let _ = stdout().flush();
@@ -1,7 +1,9 @@
+use std::borrow::Cow;
 use std::fmt::{self, Arguments};
 use std::io::Write;
 
 use colored::{Color, Colorize};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 use crate::term::node::Status;
 
@@ -29,9 +31,32 @@ pub trait Render {
     }
 }
 
+/// Shortens the text to `max` columns (including the `...`), splitting on a char boundary.
+fn cap(text: &str, max: usize) -> Cow<'_, str> {
+    if text.width() <= max {
+        return Cow::Borrowed(text);
+    }
+
+    let budget = max.saturating_sub(3);
+    let mut width = 0;
+    let end = text
+        .char_indices()
+        .find(|(_, c)| {
+            width += c.width().unwrap_or(0);
+            width > budget
+        })
+        .map_or(text.len(), |(i, _)| i);
+
+    Cow::Owned(format!("{}{}", &text[..end], &"..."[..max.min(3)]))
+}
+
 pub struct TermRenderer<W: fmt::Write> {
     writer: W,
     color: Option<Color>,
+    /// Record terminator of the plumbing output, `None` renders for humans.
+    plumbing: Option<char>,
+    width: Option<usize>,
+    column: usize,
 }
 
 impl<W: fmt::Write> TermRenderer<W> {
@@ -39,9 +64,50 @@ impl<W: fmt::Write> TermRenderer<W> {
         Self {
             writer: w,
             color: None,
+            plumbing: super::plumbing().then(|| if super::null_terminated() { '\0' } else { '\n' }),
+            width: None,
+            column: 0,
+        }
+    }
+
+    /// Renders the plumbing output with records ending in `terminator`, `None` renders for
+    /// humans.
+    pub fn with_plumbing(mut self, terminator: Option<char>) -> Self {
+        self.plumbing = terminator;
+        self
+    }
+
+    /// Caps `Node::Capped` text to the remaining width of the line.
+    pub fn with_width(mut self, width: usize) -> Self {
+        self.width = Some(width);
+        self
+    }
+
+    /// Keeps track of the current column, only needed when capping text.
+    fn track(&mut self, text: &str) {
+        if self.width.is_none() {
+            return;
+        }
+
+        let visible = |s: &str| strip_ansi_escapes::strip_str(s).width();
+
+        match text.rfind('\n') {
+            Some(i) => self.column = visible(&text[i + 1..]),
+            None => self.column += visible(text),
         }
     }
 
+    /// Writes without applying the current color.
+    fn write_raw(&mut self, args: Arguments<'_>) -> fmt::Result {
+        let text = fmt::format(args);
+        self.track(&text);
+        self.writer.write_str(&text)
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
     pub fn render_with(&mut self, node: &Node, color: Color) -> fmt::Result {
         let state = self.color.take();
         self.color = Some(color);
@@ -52,11 +118,12 @@ impl<W: fmt::Write> TermRenderer<W> {
     }
 
     fn write_fmt(&mut self, args: Arguments<'_>) -> fmt::Result {
+        let text = fmt::format(args);
+        self.track(&text);
+
         match self.color {
-            Some(color) => self
-                .writer
-                .write_str(&fmt::format(args).color(color).to_string()),
-            None => self.writer.write_fmt(args),
+            Some(color) => self.writer.write_str(&text.color(color).to_string()),
+            None => self.writer.write_str(&text),
         }
     }
 }
@@ -73,11 +140,149 @@ macro_rules! write {
     };
 }
 
+/// Quotes text like git quotes paths, keeping each plumbing record on a single line.
+fn quote(text: &str) -> Cow<'_, str> {
+    if !text
+        .chars()
+        .any(|c| c == '"' || c == '\\' || c.is_control())
+    {
+        return Cow::Borrowed(text);
+    }
+
+    let mut quoted = String::from('"');
+
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => {
+                for byte in c.to_string().bytes() {
+                    quoted.push_str(&format!("\\{byte:03o}"));
+                }
+            }
+            c => quoted.push(c),
+        }
+    }
+
+    quoted.push('"');
+    Cow::Owned(quoted)
+}
+
+/// Spacers only align the human output, they aren't fields.
+fn is_spacer(node: &Node) -> bool {
+    match node {
+        Node::Empty => true,
+        Node::Text(text) => text.chars().all(|c| c == ' '),
+        Node::Dimmed(node) | Node::Status(_, node) => is_spacer(node),
+        _ => false,
+    }
+}
+
+impl<W: fmt::Write> TermRenderer<W> {
+    /// Renders the stable plumbing form: no colors or icons, full object ids, tab-delimited
+    /// fields and records ending in `terminator` (see "Plumbing mode" in the README).
+    fn render_plumbing(&mut self, node: &Node, terminator: char) -> fmt::Result {
+        match node {
+            Node::Text(text) | Node::Capped(text) if text.chars().all(|c| c == '\n') => {
+                for _ in text.chars() {
+                    self.writer.write_char(terminator)?;
+                }
+
+                Ok(())
+            }
+            Node::Text(text) | Node::Capped(text) => match terminator {
+                '\0' => self.writer.write_str(text),
+                _ => self.writer.write_str(&quote(text)),
+            },
+            Node::Dimmed(node) | Node::Status(_, node) | Node::Continued(node) => {
+                self.render_plumbing(node, terminator)
+            }
+            Node::Label(node) => self.render_plumbing(node, terminator),
+            Node::Block(children) | Node::Breadcrumb(children) => {
+                for (i, node) in children.iter().filter(|node| !is_spacer(node)).enumerate() {
+                    if i > 0 {
+                        self.writer.write_char('\t')?;
+                    }
+
+                    self.render_plumbing(node, terminator)?;
+                }
+
+                Ok(())
+            }
+            Node::MultiLine(children) => {
+                for (i, node) in children.iter().enumerate() {
+                    if i > 0 {
+                        self.writer.write_char(terminator)?;
+                    }
+
+                    self.render_plumbing(node, terminator)?;
+                }
+
+                Ok(())
+            }
+            Node::Attribute(attr) => match attr {
+                Attribute::Commit(oid) | Attribute::CommitShort(oid) => {
+                    self.writer.write_fmt(format_args!("{oid}"))
+                }
+                Attribute::Tag(name)
+                | Attribute::Branch(name)
+                | Attribute::Remote(name)
+                | Attribute::Operation(name) => self.writer.write_str(name),
+            },
+            Node::Group(heading, count, node) => {
+                self.writer.write_str(heading)?;
+
+                if let Some(count) = count {
+                    self.writer.write_fmt(format_args!("\t{count}"))?;
+                }
+
+                self.writer.write_char(terminator)?;
+                self.render_plumbing(node, terminator)
+            }
+            Node::Icon(icon) => self.writer.write_str(match icon {
+                Icon::ArrowUp => "ahead",
+                Icon::ArrowDown => "behind",
+                Icon::Lock => "signed",
+                Icon::Check => "ok",
+            }),
+            Node::Indicator(indicator) => self.writer.write_str(match indicator {
+                Indicator::Unknown => "?",
+                Indicator::Conflict => "U",
+                Indicator::New => "A",
+                Indicator::Modified => "M",
+                Indicator::Renamed => "R",
+                Indicator::Deleted => "D",
+                Indicator::Warning => "!",
+            }),
+            Node::Column(left, right) => {
+                self.render_plumbing(left, terminator)?;
+                self.writer.write_char('\t')?;
+                self.render_plumbing(right, terminator)
+            }
+            Node::Empty => Ok(()),
+        }
+    }
+}
+
 impl<W: fmt::Write> Render for TermRenderer<W> {
     fn render(&mut self, node: &Node) -> fmt::Result {
+        if let Some(terminator) = self.plumbing {
+            return self.render_plumbing(node, terminator);
+        }
+
         match node {
             Node::Dimmed(node) => self.render_with(node, Color::BrightBlack),
             Node::Text(text) => write!(self, "{text}"),
+            Node::Capped(text) => match self.width {
+                Some(width) => {
+                    let text = cap(text, width.saturating_sub(self.column));
+                    write!(self, "{text}")
+                }
+                None => write!(self, "{text}"),
+            },
             Node::Block(children) => {
                 for node in children {
                     self.render(node)?;
@@ -110,7 +315,9 @@ impl<W: fmt::Write> Render for TermRenderer<W> {
                         .collect::<String>()
                         .yellow()
                 ),
-                Attribute::Commit(oid) => write!(self.writer, "{}", oid.to_string().yellow()),
+                Attribute::Commit(oid) => {
+                    self.write_raw(format_args!("{}", oid.to_string().yellow()))
+                }
                 Attribute::Tag(name) => write!(self, "{}", format!("#{name}").blue()),
                 Attribute::Branch(name) => write!(self, "{}", format!(" {name}").blue()),
                 Attribute::Remote(name) => write!(self, "{}", format!("⬡ {name}").cyan()),
@@ -138,7 +345,7 @@ impl<W: fmt::Write> Render for TermRenderer<W> {
                 Ok(())
             }
             Node::Icon(icon) => match icon {
-                Icon::ArrowUp => write!(self.writer, "↑"),
+                Icon::ArrowUp => self.write_raw(format_args!("↑")),
                 Icon::ArrowDown => write!(self, "↓"),
                 Icon::Lock => write!(self, "⚿"),
                 Icon::Check => write!(self, "✓"),
@@ -150,6 +357,7 @@ impl<W: fmt::Write> Render for TermRenderer<W> {
                 Indicator::Modified => write!(self, "{}", "~".yellow()),
                 Indicator::Renamed => write!(self, "{}", "➜".yellow()),
                 Indicator::Deleted => write!(self, "{}", "✖".red()),
+                Indicator::Warning => write!(self, "{}", "!".yellow()),
             },
             Node::Status(status, node) => match status {
                 Status::Error => self.render_with(node, Color::Red),
@@ -170,3 +378,24 @@ impl<W: fmt::Write> Render for TermRenderer<W> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cap_multibyte() {
+        let title = "Fix crash on naïve café 🎉🎉🎉 titles";
+
+        assert_eq!(cap(title, 80), title);
+        assert_eq!(cap(title, 16), "Fix crash on ...");
+        assert_eq!(cap(title, 18), "Fix crash on na...");
+        assert_eq!(cap(title, 30), "Fix crash on naïve café 🎉...");
+        assert_eq!(cap("🎉🎉🎉🎉", 6), "🎉...");
+
+        let mut ui = TermRenderer::new(String::new()).with_width(12);
+        ui.render(&Node::Capped(title.into())).unwrap();
+
+        assert_eq!(ui.into_inner(), "Fix crash...");
+    }
+}
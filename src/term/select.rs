@@ -1,16 +1,78 @@
-use std::{error::Error, io::Cursor};
+use std::{env, error::Error, io::Cursor};
 
 use skim::{
-    prelude::{Event, SkimItemReader, SkimOptionsBuilder},
+    prelude::{Event, SkimItemReader, SkimOptions, SkimOptionsBuilder},
     Skim,
 };
 
-pub fn single(input: &[String], preview: Option<String>) -> Result<Option<String>, Box<dyn Error>> {
-    let options = SkimOptionsBuilder::default()
-        .exit_0(true)
-        .multi(false)
-        .preview(preview)
-        .build()?;
+use crate::git::Optional;
+
+#[derive(Default)]
+pub struct SelectOpts {
+    /// Command to preview the highlighted item with, `{}` is replaced with the item.
+    pub preview: Option<String>,
+    /// Size and position of the preview window (e.g. `right:50%` or `up:40%`).
+    pub preview_window: Option<String>,
+    /// Field delimiter, fields can be referenced in the preview command as `{1}`, `{2}`, etc.
+    pub delimiter: Option<String>,
+}
+
+impl SelectOpts {
+    /// Previews items by running `args` with the current executable. The preview can be overridden
+    /// with `src.<name>Preview`, `src.previewWindow` sets the layout and `src.preview = false`
+    /// turns previews off.
+    pub fn from_config(config: &git2::Config, name: &str, args: &str) -> Result<Self, git2::Error> {
+        if !config.get_bool("src.preview").optional()?.unwrap_or(true) {
+            return Ok(Self::default());
+        }
+
+        let preview = match config
+            .get_string(&format!("src.{name}Preview"))
+            .optional()?
+        {
+            Some(preview) => preview,
+            None => {
+                let exe = env::current_exe()
+                    .map(|path| format!("'{}'", path.display()))
+                    .unwrap_or_else(|_| "src".to_string());
+
+                format!("{exe} {args}")
+            }
+        };
+
+        Ok(Self {
+            preview: Some(preview),
+            preview_window: config.get_string("src.previewWindow").optional()?,
+            delimiter: None,
+        })
+    }
+
+    pub fn with_delimiter(mut self, delimiter: &str) -> Self {
+        self.delimiter = Some(delimiter.to_string());
+        self
+    }
+
+    fn options(&self, multi: bool) -> Result<SkimOptions, Box<dyn Error>> {
+        let mut builder = SkimOptionsBuilder::default();
+        builder
+            .exit_0(true)
+            .multi(multi)
+            .preview(self.preview.clone());
+
+        if let Some(window) = &self.preview_window {
+            builder.preview_window(window.clone());
+        }
+
+        if let Some(delimiter) = &self.delimiter {
+            builder.delimiter(delimiter.clone());
+        }
+
+        Ok(builder.build()?)
+    }
+}
+
+pub fn single(input: &[String], opts: &SelectOpts) -> Result<Option<String>, Box<dyn Error>> {
+    let options = opts.options(false)?;
     let reader = SkimItemReader::default();
     let items = reader.of_bufread(Cursor::new(input.join("\n")));
 
@@ -25,12 +87,8 @@ pub fn single(input: &[String], preview: Option<String>) -> Result<Option<String
     }))
 }
 
-pub fn multi(input: &[String], preview: Option<String>) -> Result<Vec<String>, Box<dyn Error>> {
-    let options = SkimOptionsBuilder::default()
-        .exit_0(true)
-        .multi(true)
-        .preview(preview)
-        .build()?;
+pub fn multi(input: &[String], opts: &SelectOpts) -> Result<Vec<String>, Box<dyn Error>> {
+    let options = opts.options(true)?;
     let reader = SkimItemReader::default();
     let items = reader.of_bufread(Cursor::new(input.join("\n")));
 